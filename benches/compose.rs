@@ -0,0 +1,83 @@
+//! Compares composing bins row by row against composing them one pixel at a time.
+//!
+//! Run with `cargo bench --bench compose`.
+
+extern crate image;
+extern crate texture_atlas;
+
+use std::time::{Duration, Instant};
+
+use image::{DynamicImage, GenericImage, Rgba};
+use texture_atlas::Atlas;
+use texture_atlas::gen::BinaryTreeGenerator;
+
+const PAGE_SIZE: u32 = 4096;
+const SPRITE_SIZE: u32 = 256;
+const ITERATIONS: u32 = 3;
+
+/// Composes a bin one pixel at a time, the way images were composed originally.
+fn compose_per_pixel(atlas: &Atlas<DynamicImage>, bin_index: usize) -> DynamicImage
+{
+	let bin = &atlas.bin_list()[bin_index];
+	let mut image = DynamicImage::new_rgba8(bin.dimensions().width, bin.dimensions().height);
+	for part in bin.part_list()
+	{
+		let texture = &atlas.rect_list()[part.rect_index];
+		for x in 0..texture.width()
+		{
+			for y in 0..texture.height()
+			{
+				let pixel = texture.get_pixel(x, y);
+				if !part.rotate
+				{
+					image.put_pixel(part.x + x, part.y + y, pixel);
+				}
+				else
+				{
+					image.put_pixel(part.x + (texture.height() - 1 - y), part.y + x, pixel);
+				}
+			}
+		}
+	}
+	image
+}
+
+fn time<F: FnMut()>(mut function: F) -> Duration
+{
+	let start = Instant::now();
+	for _ in 0..ITERATIONS
+	{
+		function();
+	}
+	start.elapsed() / ITERATIONS
+}
+
+fn main()
+{
+	// Fill a full page with sprites, half of which are rotated.
+	let count = (PAGE_SIZE / SPRITE_SIZE) * (PAGE_SIZE / SPRITE_SIZE);
+	let mut rect_list = Vec::with_capacity(count as usize);
+	for index in 0..count
+	{
+		let (width, height) = if index % 2 == 0 { (SPRITE_SIZE, SPRITE_SIZE / 2) } else { (SPRITE_SIZE / 2, SPRITE_SIZE) };
+		let mut image = DynamicImage::new_rgba8(width, height);
+		for x in 0..width
+		{
+			for y in 0..height
+			{
+				image.put_pixel(x, y, Rgba::<u8> { data: [x as u8, y as u8, index as u8, 255] });
+			}
+		}
+		rect_list.push(image);
+	}
+
+	let atlas = Atlas::build(&rect_list, PAGE_SIZE, PAGE_SIZE, true).generate(&BinaryTreeGenerator::new()).unwrap();
+	let rotated = atlas.bin_list()[0].part_list().iter().filter(|part| part.rotate).count();
+	println!("{} bins, {} of {} parts rotated in the first bin", atlas.bin_list().len(), rotated, atlas.bin_list()[0].part_list().len());
+
+	let per_pixel = time(|| { compose_per_pixel(&atlas, 0); });
+	let rows = time(|| { atlas.bin_as_rgba(0); });
+	println!("per pixel: {:?}", per_pixel);
+	println!("rows:      {:?}", rows);
+	println!("speedup:   {:.1}x", per_pixel.as_secs_f64() / rows.as_secs_f64());
+}
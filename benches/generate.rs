@@ -0,0 +1,46 @@
+//! Runs every generator over the reference corpora shared with the tests.
+//!
+//! Reports the time taken, the number of bins along with their lower bound, the occupancy of all
+//! bins and the bounding area of the last bin. Run with `cargo bench --bench generate`.
+
+extern crate texture_atlas;
+
+#[path = "../tests/corpus/mod.rs"]
+mod corpus;
+
+use std::time::{Duration, Instant};
+
+use corpus::Corpus;
+use texture_atlas::{Atlas, AtlasGenerator, AtlasRect};
+use texture_atlas::gen::{AnnealingGenerator, BinaryTreeGenerator, OptimalGenerator, PassthroughGenerator};
+
+const ITERATIONS: u32 = 5;
+
+fn run<G: AtlasGenerator>(name: &str, generator: &G, corpus: &Corpus)
+{
+	let mut atlas = None;
+	let start = Instant::now();
+	for _ in 0..ITERATIONS
+	{
+		atlas = Some(Atlas::build(&corpus.rect_list, corpus.width, corpus.height, true).generate(generator).unwrap());
+	}
+	let elapsed: Duration = start.elapsed() / ITERATIONS;
+	let atlas = atlas.unwrap();
+
+	let stats = atlas.stats();
+	let last_area = atlas.bin_list().last().map_or(0, |bin| (bin as &AtlasRect).area());
+	println!("{:<18} {:<14} {:>12?} {:>6} {:>6} {:>9.1}% {:>10}", corpus.name, name, elapsed, atlas.bin_list().len(), atlas.lower_bound().unwrap(),
+		100.0 * stats.occupancy, last_area);
+}
+
+fn main()
+{
+	println!("{:<18} {:<14} {:>12} {:>6} {:>6} {:>10} {:>10}", "corpus", "generator", "time", "bins", "bound", "occupancy", "last area");
+	for corpus in corpus::corpus_list()
+	{
+		run("passthrough", &PassthroughGenerator, &corpus);
+		run("binary-tree", &BinaryTreeGenerator::new(), &corpus);
+		run("optimal", &OptimalGenerator::new().node_limit(10_000), &corpus);
+		run("annealing", &AnnealingGenerator::new(BinaryTreeGenerator::new()), &corpus);
+	}
+}
@@ -0,0 +1,465 @@
+//! Runtime atlas allocation.
+
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+
+use {Atlas, AtlasBin, AtlasGenerator, AtlasRect};
+use gen::{BinaryTreeGenerator, Rectr, SplitPolicy};
+use util::{Rect, Region};
+
+/// Identifies an allocation made by a `DynamicAtlas`.
+///
+/// The index of a deallocated rect is reused by later allocations, but with a new generation, so
+/// that identifiers of deallocated rects never refer to the allocations that replace them.
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct AllocId(usize, u32);
+
+impl AllocId
+{
+	/// Returns the index of this allocation, which parts of each page use as their rect index.
+	pub fn index(&self) -> usize
+	{
+		self.0
+	}
+
+	/// Returns the number of times that the index of this allocation was deallocated before.
+	pub fn generation(&self) -> u32
+	{
+		self.1
+	}
+}
+
+/// A rect moved to a new position by `DynamicAtlas::compact`.
+#[derive(Copy, Clone, Debug)]
+pub struct AllocMove
+{
+	/// The allocation that was moved.
+	pub id: AllocId,
+
+	/// The page index the rect was previously in.
+	pub source_page: usize,
+
+	/// The previous x-position of the rect.
+	pub source_x: u32,
+
+	/// The previous y-position of the rect.
+	pub source_y: u32,
+
+	/// The page index the rect is now in.
+	pub target_page: usize,
+
+	/// The new x-position of the rect.
+	pub target_x: u32,
+
+	/// The new y-position of the rect.
+	pub target_y: u32,
+}
+
+/// A long-lived allocator of rects in fixed size pages.
+///
+/// Unlike `Atlas`, which is generated once from a complete list of rects, this allocator places and
+/// frees rects one at a time, for instance for glyphs or streamed decals. Each page is an
+/// `AtlasBin` whose parts reference allocations by their index.
+///
+/// Free space is tracked using the same leaves as `BinaryTreeGenerator`. When a rect is freed, its
+/// leaf is merged with every free neighbour sharing an entire edge so that the space can be reused
+/// by larger rects. New pages are added when no leaf fits, up to an optional page limit.
+///
+pub struct DynamicAtlas
+{
+	dimensions: Rect,
+	max_pages: Option<usize>,
+	page_list: Vec<AtlasBin>,
+	leaves: Vec<Rectr>,
+	alloc_list: Vec<Option<(usize, Region)>>,
+	alloc_generation_list: Vec<u32>,
+	alloc_free_list: Vec<usize>,
+}
+
+impl DynamicAtlas
+{
+	/// Creates an allocator without any pages where each page has the given dimensions.
+	pub fn new(width: u32, height: u32) -> Self
+	{
+		DynamicAtlas
+		{
+			dimensions: Rect::new(width, height),
+			max_pages: None,
+			page_list: Vec::new(),
+			leaves: Vec::new(),
+			alloc_list: Vec::new(),
+			alloc_generation_list: Vec::new(),
+			alloc_free_list: Vec::new(),
+		}
+	}
+
+	/// Limits the number of pages that the allocator may create.
+	pub fn max_pages(mut self, max_pages: usize) -> Self
+	{
+		self.max_pages = Some(max_pages);
+		self
+	}
+
+	/// Returns the dimensions of every page.
+	pub fn dimensions(&self) -> Rect
+	{
+		self.dimensions
+	}
+
+	/// Returns the pages that contain the allocated rects.
+	pub fn page_list(&self) -> &[AtlasBin]
+	{
+		&self.page_list
+	}
+
+	/// Returns the page index and region of the indicated allocation, if it is still allocated.
+	pub fn region(&self, id: AllocId) -> Option<(usize, Region)>
+	{
+		if !self.is_current(id)
+		{
+			return None;
+		}
+		self.alloc_list[id.0]
+	}
+
+	/// Returns every live allocation with its page index and region.
+	pub fn alloc_list(&self) -> Vec<(AllocId, usize, Region)>
+	{
+		self.alloc_list.iter()
+			.enumerate()
+			.filter_map(|(index, alloc)| alloc.map(|(page, region)| (AllocId(index, self.alloc_generation_list[index]), page, region)))
+			.collect()
+	}
+
+	/// Allocates a rect with the given dimensions.
+	///
+	/// Returns `None` if the rect is empty, larger than a page or if no page has room for it and no
+	/// more pages can be added.
+	///
+	pub fn allocate(&mut self, width: u32, height: u32) -> Option<AllocId>
+	{
+		if width == 0 || height == 0 || width > self.dimensions.width || height > self.dimensions.height
+		{
+			return None;
+		}
+
+		let mut leaf_found = self.leaves.iter().position(|leaf| width <= leaf.width && height <= leaf.height);
+		if leaf_found.is_none() && self.max_pages.is_none_or(|max_pages| self.page_list.len() < max_pages)
+		{
+			let page = self.page_list.len();
+			self.page_list.push(AtlasBin::new(self.dimensions, Vec::new()));
+			self.leaves.push(Rectr::new(page, 0, 0, self.dimensions.width, self.dimensions.height));
+			leaf_found = Some(self.leaves.len() - 1);
+		}
+
+		leaf_found.map(|leaf_index|
+		{
+			let leaf = self.leaves[leaf_index];
+			BinaryTreeGenerator::subdivide(&mut self.leaves, leaf_index, width, height, SplitPolicy::Horizontal);
+
+			let index = self.alloc_free_list.pop().unwrap_or_else(||
+			{
+				self.alloc_list.push(None);
+				self.alloc_generation_list.push(0);
+				self.alloc_list.len() - 1
+			});
+			self.alloc_list[index] = Some((leaf.bin, Region::new(leaf.x, leaf.y, width, height)));
+			self.page_list[leaf.bin].part_add(index, leaf.x, leaf.y, Rect::new(width, height), false);
+			AllocId(index, self.alloc_generation_list[index])
+		})
+	}
+
+	/// Frees the indicated allocation so that its space can be reused.
+	///
+	/// Deallocating an identifier that is not allocated, or whose index was reused since, does
+	/// nothing.
+	///
+	pub fn deallocate(&mut self, id: AllocId)
+	{
+		if !self.is_current(id)
+		{
+			return;
+		}
+		if let Some((page, region)) = self.alloc_list[id.0].take()
+		{
+			self.alloc_generation_list[id.0] = self.alloc_generation_list[id.0].wrapping_add(1);
+			let part_list = &mut self.page_list[page].part_list;
+			if let Some(part_index) = part_list.iter().position(|part| part.rect_index == id.0)
+			{
+				part_list.remove(part_index);
+			}
+			self.alloc_free_list.push(id.0);
+			self.leaf_free(Rectr::new(page, region.x, region.y, region.width, region.height));
+		}
+	}
+
+	/// Repacks the live rects with the given generator to undo fragmentation.
+	///
+	/// To keep the number of moves low, the most used pages are pinned in place and only the rects
+	/// of the remaining pages are repacked around them. Every number of pinned pages is tried and the
+	/// result with the fewest pages, then the fewest moves, is kept. Pinned pages keep their index.
+	///
+	/// Returns the rects that moved. Since targets may overlap sources of other moves, every source
+	/// region should be copied before any target region is written.
+	///
+	pub fn compact<G: AtlasGenerator>(&mut self, generator: &G) -> Vec<AllocMove>
+	{
+		let alloc_list = self.alloc_list();
+		let rect_list: Vec<Rect> = alloc_list.iter().map(|&(_, _, region)| region.dimensions()).collect();
+
+		// Pages ordered from the most to the least used area.
+		let mut used_list = vec![0; self.page_list.len()];
+		for &(_, page, region) in &alloc_list
+		{
+			used_list[page] += (&region as &dyn AtlasRect).area();
+		}
+		let mut page_order: Vec<usize> = (0..self.page_list.len()).collect();
+		page_order.sort_by(|&left, &right| used_list[right].cmp(&used_list[left]).then(left.cmp(&right)));
+
+		let mut best: Option<(usize, usize, Atlas<Rect>)> = None;
+		for pinned_count in (0..page_order.len() + 1).rev()
+		{
+			let pinned_list = &page_order[..pinned_count];
+			let mut builder = Atlas::build(&rect_list, self.dimensions.width, self.dimensions.height, false);
+			if let Some(max_pages) = self.max_pages
+			{
+				builder = builder.max_bins(max_pages);
+			}
+			for (rect_index, &(_, page, region)) in alloc_list.iter().enumerate()
+			{
+				if pinned_list.contains(&page)
+				{
+					builder = builder.pin(rect_index, page, region.x, region.y, false);
+				}
+			}
+
+			let atlas = match builder.generate(generator)
+			{
+				Ok(atlas) => atlas,
+				Err(_) => continue,
+			};
+			let move_count = alloc_list.iter().enumerate().filter(|&(rect_index, &(_, page, region))|
+			{
+				let (bin_index, part) = atlas.rect_part(rect_index).unwrap();
+				(bin_index, part.x, part.y) != (page, region.x, region.y)
+			}).count();
+
+			let bin_count = atlas.bin_list().len();
+			if best.as_ref().is_none_or(|&(best_bins, best_moves, _)| (bin_count, move_count) < (best_bins, best_moves))
+			{
+				best = Some((bin_count, move_count, atlas));
+			}
+		}
+
+		let atlas = match best
+		{
+			Some((_, _, atlas)) => atlas,
+			None => return Vec::new(),
+		};
+
+		let mut move_list = Vec::new();
+		self.page_list = (0..atlas.bin_list().len()).map(|_| AtlasBin::new(self.dimensions, Vec::new())).collect();
+		for (rect_index, &(id, page, region)) in alloc_list.iter().enumerate()
+		{
+			let (bin_index, part) = atlas.rect_part(rect_index).unwrap();
+			if (bin_index, part.x, part.y) != (page, region.x, region.y)
+			{
+				move_list.push(AllocMove
+				{
+					id,
+					source_page: page,
+					source_x: region.x,
+					source_y: region.y,
+					target_page: bin_index,
+					target_x: part.x,
+					target_y: part.y,
+				});
+			}
+			self.alloc_list[id.0] = Some((bin_index, Region::new(part.x, part.y, region.width, region.height)));
+			self.page_list[bin_index].part_add(id.0, part.x, part.y, region.dimensions(), false);
+		}
+
+		self.leaves.clear();
+		for page in 0..atlas.bin_list().len()
+		{
+			let occupied_list = atlas.bin_occupied_list(page);
+			let leaves = BinaryTreeGenerator::leaves_bin(page, self.dimensions.width, self.dimensions.height, &occupied_list);
+			self.leaves.extend(leaves);
+		}
+		move_list
+	}
+
+	/// Returns true if the given identifier has the current generation of its index.
+	fn is_current(&self, id: AllocId) -> bool
+	{
+		self.alloc_generation_list.get(id.0) == Some(&id.1)
+	}
+
+	/// Adds the given leaf to the free leaves after merging it with its neighbours.
+	fn leaf_free(&mut self, mut leaf: Rectr)
+	{
+		loop
+		{
+			let neighbour_found = self.leaves.iter().position(|other|
+			{
+				let horizontal = other.y == leaf.y && other.height == leaf.height &&
+					(other.x + other.width == leaf.x || leaf.x + leaf.width == other.x);
+				let vertical = other.x == leaf.x && other.width == leaf.width &&
+					(other.y + other.height == leaf.y || leaf.y + leaf.height == other.y);
+				other.bin == leaf.bin && (horizontal || vertical)
+			});
+			let other = match neighbour_found
+			{
+				Some(neighbour_index) => self.leaves.remove(neighbour_index),
+				None => break,
+			};
+
+			let x = ::std::cmp::min(leaf.x, other.x);
+			let y = ::std::cmp::min(leaf.y, other.y);
+			if other.y == leaf.y && other.height == leaf.height
+			{
+				leaf = Rectr::new(leaf.bin, x, y, leaf.width + other.width, leaf.height);
+			}
+			else
+			{
+				leaf = Rectr::new(leaf.bin, x, y, leaf.width, leaf.height + other.height);
+			}
+		}
+		self.leaves.push(leaf);
+	}
+}
+
+/// A cache of rects keyed by user keys, such as rasterized glyphs, that evicts the least recently
+/// used rects when its allocator runs out of space.
+///
+/// Usage is tracked per frame. Rects used during the current frame are never evicted, since they
+/// may still be drawn. Evicted rects are recorded so that the pages can be patched on the GPU.
+///
+pub struct AtlasCache<K>
+{
+	atlas: DynamicAtlas,
+	frame: u64,
+	entry_map: HashMap<K, (AllocId, u64)>,
+	usage_map: BTreeMap<(u64, AllocId), K>,
+	invalidated_list: Vec<(K, usize, Region)>,
+}
+
+impl<K> AtlasCache<K> where K: Eq + Hash + Clone
+{
+	/// Creates an empty cache on top of the given allocator.
+	///
+	/// The allocator should usually have a page limit, otherwise nothing is ever evicted.
+	///
+	pub fn new(atlas: DynamicAtlas) -> Self
+	{
+		AtlasCache
+		{
+			atlas,
+			frame: 0,
+			entry_map: HashMap::new(),
+			usage_map: BTreeMap::new(),
+			invalidated_list: Vec::new(),
+		}
+	}
+
+	/// Returns the underlying allocator.
+	pub fn atlas(&self) -> &DynamicAtlas
+	{
+		&self.atlas
+	}
+
+	/// Returns the number of cached rects.
+	pub fn len(&self) -> usize
+	{
+		self.entry_map.len()
+	}
+
+	/// Returns true if no rects are cached.
+	pub fn is_empty(&self) -> bool
+	{
+		self.entry_map.is_empty()
+	}
+
+	/// Starts a new frame. Rects used in previous frames become candidates for eviction.
+	pub fn frame_next(&mut self)
+	{
+		self.frame += 1;
+	}
+
+	/// Returns the page index and region of the indicated rect and marks it as used this frame.
+	pub fn get(&mut self, key: &K) -> Option<(usize, Region)>
+	{
+		let frame = self.frame;
+		let id = match self.entry_map.get_mut(key)
+		{
+			Some(&mut (id, ref mut used)) =>
+			{
+				let key = self.usage_map.remove(&(*used, id)).unwrap();
+				self.usage_map.insert((frame, id), key);
+				*used = frame;
+				id
+			}
+			None => return None,
+		};
+		self.atlas.region(id)
+	}
+
+	/// Allocates a rect for the given key and marks it as used this frame.
+	///
+	/// If there is no room, least recently used rects are evicted until the rect fits. Returns `None`
+	/// if the rect still does not fit after evicting every rect not used during this frame, or right
+	/// away without evicting anything if the rect is empty or larger than a page. A rect already
+	/// cached under the same key is replaced.
+	///
+	pub fn insert(&mut self, key: K, width: u32, height: u32) -> Option<(usize, Region)>
+	{
+		self.remove(&key);
+		let dimensions = self.atlas.dimensions();
+		if width == 0 || height == 0 || width > dimensions.width || height > dimensions.height
+		{
+			return None;
+		}
+		loop
+		{
+			if let Some(id) = self.atlas.allocate(width, height)
+			{
+				self.entry_map.insert(key.clone(), (id, self.frame));
+				self.usage_map.insert((self.frame, id), key);
+				return self.atlas.region(id);
+			}
+
+			let evicted = match self.usage_map.keys().next()
+			{
+				Some(&(used, id)) if used < self.frame => (used, id),
+				_ => return None,
+			};
+			let key_evicted = self.usage_map.remove(&evicted).unwrap();
+			let (page, region) = self.atlas.region(evicted.1).unwrap();
+			self.entry_map.remove(&key_evicted);
+			self.atlas.deallocate(evicted.1);
+			self.invalidated_list.push((key_evicted, page, region));
+		}
+	}
+
+	/// Removes the indicated rect from the cache. Returns true if it was cached.
+	pub fn remove(&mut self, key: &K) -> bool
+	{
+		match self.entry_map.remove(key)
+		{
+			Some((id, used)) =>
+			{
+				self.usage_map.remove(&(used, id));
+				self.atlas.deallocate(id);
+				true
+			}
+			None => false,
+		}
+	}
+
+	/// Returns the rects evicted since the last call, with the page index and region they occupied.
+	pub fn invalidated_take(&mut self) -> Vec<(K, usize, Region)>
+	{
+		::std::mem::take(&mut self.invalidated_list)
+	}
+}
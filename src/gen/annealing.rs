@@ -0,0 +1,179 @@
+use std::cmp::max;
+
+use {Atlas, AtlasGenerator, AtlasRect};
+use gen::OrderedGenerator;
+use util::Rng;
+
+/// The number of energy units in a bin. Energies and temperatures are fixed-point numbers, so that
+/// the search does not depend on floating point functions that differ between platforms.
+const ENERGY_SCALE: u64 = 1 << 16;
+
+/// The temperature at the first iteration, 0.3 bins.
+const TEMPERATURE_START: u64 = 19_661;
+
+/// The number of times the temperature halves between the first and the last iteration in energy
+/// units, so that it ends at 0.003 bins.
+const TEMPERATURE_HALVINGS: u64 = 435_412;
+
+/// The binary logarithm of e, in energy units.
+const LOG2_E: u64 = 94_548;
+
+/// Powers of two between 1 and 1/2 in steps of a sixteenth of an exponent, scaled by 2^32.
+const POWER_TABLE: [u64; 16] =
+[
+	4_294_967_296, 4_112_874_773, 3_938_502_376, 3_771_522_796, 3_611_622_603, 3_458_501_653, 3_311_872_529, 3_171_459_999,
+	3_037_000_500, 2_908_241_642, 2_784_941_738, 2_666_869_345, 2_553_802_834, 2_445_529_972, 2_341_847_524, 2_242_560_872,
+];
+
+/// Returns two to the power of the given negative exponent in energy units, scaled by 2^32.
+fn power_negative(exponent: u64) -> u64
+{
+	let shift = exponent / ENERGY_SCALE;
+	if shift >= 64
+	{
+		return 0;
+	}
+	POWER_TABLE[(exponent % ENERGY_SCALE * 16 / ENERGY_SCALE) as usize] >> shift
+}
+
+/// Returns the number of unplaced rects, the number of bins and the fill of the last bin.
+///
+/// The fill is the ratio of the area taken up in the last bin to the maximal bin area in energy
+/// units, so that a nearly empty last bin is closer to being removed.
+///
+fn score<T: AtlasRect>(atlas: &Atlas<T>, width: u32, height: u32) -> (usize, usize, u64)
+{
+	let unplaced_count = atlas.rect_placed_list().iter().filter(|&&placed| !placed).count();
+	let fill = atlas.bin_list().last().map_or(0, |bin|
+	{
+		let used_area: u64 = bin.part_list().iter().map(|part|
+		{
+			let dimensions = atlas.rect_dimensions(part.rect_index, part.rotate);
+			dimensions.width as u64 * dimensions.height as u64
+		}).sum();
+		(used_area as u128 * ENERGY_SCALE as u128 / (width as u128 * height as u128)) as u64
+	});
+	(unplaced_count, atlas.bin_list().len(), fill)
+}
+
+/// Returns the energy of a score, where every unplaced rect costs as much as a bin.
+fn energy(score: (usize, usize, u64)) -> u64
+{
+	(score.0 + score.1) as u64 * ENERGY_SCALE + score.2
+}
+
+/// A generator that improves an inner generator by searching for better orders and rotations.
+///
+/// Greedy generators depend heavily on the order that rects are placed in. This generator starts
+/// from the order of the inner generator and uses simulated annealing to swap and move rects in the
+/// order and, if allowed, to flip their rotations. Results are scored by the number of bins and
+/// then by the fill of the last bin, keeping the best result seen.
+///
+/// The search is seeded and only uses integer arithmetic, so the same seed always generates the
+/// same bins, on every platform. Every iteration runs the inner generator once.
+///
+pub struct AnnealingGenerator<G: OrderedGenerator>
+{
+	generator: G,
+	seed: u64,
+	iteration_limit: usize,
+}
+
+impl<G: OrderedGenerator> AnnealingGenerator<G>
+{
+	/// Creates a generator around the given generator, running 1000 iterations with a seed of 0.
+	pub fn new(generator: G) -> Self
+	{
+		AnnealingGenerator
+		{
+			generator,
+			seed: 0,
+			iteration_limit: 1000,
+		}
+	}
+
+	/// Sets the seed of the random number generator.
+	pub fn seed(mut self, seed: u64) -> Self
+	{
+		self.seed = seed;
+		self
+	}
+
+	/// Sets the number of iterations, each running the inner generator once.
+	pub fn iteration_limit(mut self, iteration_limit: usize) -> Self
+	{
+		self.iteration_limit = iteration_limit;
+		self
+	}
+
+	/// Changes the given order into a random neighbour.
+	fn neighbour(rng: &mut Rng, order_list: &mut Vec<(usize, bool)>, rotate: bool)
+	{
+		let length = order_list.len();
+		let choice = rng.below(if rotate { 3 } else { 2 });
+		if choice == 2 || length < 2
+		{
+			let index = rng.below(length);
+			order_list[index].1 = !order_list[index].1 && rotate;
+		}
+		else if choice == 0
+		{
+			let (first, second) = (rng.below(length), rng.below(length));
+			order_list.swap(first, second);
+		}
+		else
+		{
+			let item = order_list.remove(rng.below(length));
+			order_list.insert(rng.below(length), item);
+		}
+	}
+}
+
+impl<G: OrderedGenerator> AtlasGenerator for AnnealingGenerator<G>
+{
+	fn generate<T: AtlasRect>(&self, atlas: &mut Atlas<T>, width: u32, height: u32, rotate: bool)
+	{
+		let mut order_list = self.generator.order_list(atlas, rotate);
+		let mut best = atlas.clone();
+		self.generator.generate_order(&mut best, width, height, rotate, &order_list);
+		if order_list.is_empty() || (order_list.len() < 2 && !rotate) || width == 0 || height == 0
+		{
+			*atlas = best;
+			return;
+		}
+
+		let mut rng = Rng::new(self.seed);
+		let mut best_score = score(&best, width, height);
+		let mut current_energy = energy(best_score);
+		for iteration in 0..self.iteration_limit
+		{
+			let mut candidate_list = order_list.clone();
+			AnnealingGenerator::<G>::neighbour(&mut rng, &mut candidate_list, rotate);
+			let mut candidate = atlas.clone();
+			self.generator.generate_order(&mut candidate, width, height, rotate, &candidate_list);
+			let candidate_score = score(&candidate, width, height);
+			let candidate_energy = energy(candidate_score);
+
+			// Worse orders are accepted less often as the temperature cools down, with a chance of
+			// e^(-difference / temperature) computed as a power of two.
+			let halvings = (iteration as u128 * TEMPERATURE_HALVINGS as u128 / self.iteration_limit as u128) as u64;
+			let temperature = max((TEMPERATURE_START * power_negative(halvings)) >> 32, 1);
+			let accept = candidate_energy <= current_energy ||
+			{
+				let exponent = (candidate_energy - current_energy) as u128 * LOG2_E as u128 / temperature as u128;
+				exponent < 64 * ENERGY_SCALE as u128 && (rng.next_u64() >> 32) < power_negative(exponent as u64)
+			};
+			if accept
+			{
+				order_list = candidate_list;
+				current_energy = candidate_energy;
+			}
+			if candidate_score < best_score
+			{
+				best = candidate;
+				best_score = candidate_score;
+			}
+		}
+		*atlas = best;
+	}
+}
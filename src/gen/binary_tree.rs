@@ -1,62 +1,21 @@
-use std::cmp::Ordering;
-
 use {Atlas, AtlasGenerator, AtlasRect};
-use util::{Rect, RotatableRect};
+use gen::{self, OrderedGenerator, SortPolicy};
+use util::{Rect, Region};
 
+/// A free leaf of a bin.
 #[derive(Debug, Clone, Copy)]
-struct RectReference<T: AsRef<Rect>>
-{
-	index: usize,
-	rect: T,
-}
-
-impl<T> AsRef<Rect> for RectReference<T> where T: AsRef<Rect>
+pub(crate) struct Rectr
 {
-	fn as_ref(&self) -> &Rect
-	{
-		self.rect.as_ref()
-	}
-}
-
-fn sort_by_longest_width_increasing<T: AtlasRect>(atlas: &Atlas<T>, rotate: bool) -> Vec<RectReference<RotatableRect>>
-{
-	let mut rect_list = Vec::with_capacity(atlas.rect_list().len());
-	for (index, rect) in atlas.rect_list().iter().enumerate()
-	{
-		rect_list.push(RectReference
-		{
-			index,
-			rect: (rect as &AtlasRect).dimensions_longest_rotated(rotate),
-		})
-	}
-	rect_list.sort_by(|ref_left, ref_right|
-	{
-		let height_cmp = ref_right.rect.height().cmp(&ref_left.rect.height());
-		if height_cmp == Ordering::Equal
-		{
-			ref_right.rect.width().cmp(&ref_left.rect.width())
-		}
-		else
-		{
-			height_cmp
-		}
-	});
-	rect_list
-}
-
-#[derive(Debug, Clone, Copy)]
-struct Rectr
-{
-	bin: usize,
-	x: u32,
-	y: u32,
-	width: u32,
-	height: u32,
+	pub(crate) bin: usize,
+	pub(crate) x: u32,
+	pub(crate) y: u32,
+	pub(crate) width: u32,
+	pub(crate) height: u32,
 }
 
 impl Rectr
 {
-	fn new(bin: usize, x: u32, y: u32, width: u32, height: u32) -> Self
+	pub(crate) fn new(bin: usize, x: u32, y: u32, width: u32, height: u32) -> Self
 	{
 		Rectr
 		{
@@ -71,6 +30,57 @@ impl Rectr
 	{
 		self.width == 0 || self.height == 0
 	}
+	pub(crate) fn region(&self) -> Region
+	{
+		Region::new(self.x, self.y, self.width, self.height)
+	}
+}
+
+/// How a leaf is split into two after a rect is placed in its top-left corner.
+///
+/// A horizontal split gives the leaf below the rect the full width of the leaf, while a vertical
+/// split gives the leaf to the right of the rect the full height of the leaf.
+///
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum SplitPolicy
+{
+	/// Always split horizontally.
+	#[default]
+	Horizontal,
+
+	/// Always split vertically.
+	Vertical,
+
+	/// Split along the axis with less space left over.
+	ShorterLeftoverAxis,
+
+	/// Split along the axis with more space left over.
+	LongerLeftoverAxis,
+
+	/// Split so that the smaller of the two leaves is as small as possible.
+	MinimizeArea,
+
+	/// Split so that the smaller of the two leaves is as large as possible.
+	MaximizeArea,
+}
+
+impl SplitPolicy
+{
+	/// Returns whether a leaf of the given size is split horizontally after placing the given size.
+	fn horizontal(&self, leaf: Rectr, width: u32, height: u32) -> bool
+	{
+		let leftover_width = (leaf.width - width) as u64;
+		let leftover_height = (leaf.height - height) as u64;
+		match *self
+		{
+			SplitPolicy::Horizontal => true,
+			SplitPolicy::Vertical => false,
+			SplitPolicy::ShorterLeftoverAxis => leftover_width <= leftover_height,
+			SplitPolicy::LongerLeftoverAxis => leftover_width > leftover_height,
+			SplitPolicy::MinimizeArea => width as u64 * leftover_height > leftover_width * height as u64,
+			SplitPolicy::MaximizeArea => width as u64 * leftover_height <= leftover_width * height as u64,
+		}
+	}
 }
 
 /// A generator that uses binary trees to generate an atlas.
@@ -78,17 +88,135 @@ impl Rectr
 /// This generator is useful for when decent results are needed but good speed is required. This
 /// algorithm works well when rects are uniformly sized, otherwise it will leave a lot of gaps.
 ///
-pub struct BinaryTreeGenerator;
+pub struct BinaryTreeGenerator
+{
+	sort: SortPolicy,
+	split: SplitPolicy,
+}
 
 impl BinaryTreeGenerator
 {
-	fn subdivide(leaves: &mut Vec<Rectr>, leaf_index: usize, width: u32, height: u32)
+	/// Creates a generator placing rects by descending height and splitting leaves horizontally.
+	pub fn new() -> Self
+	{
+		BinaryTreeGenerator
+		{
+			sort: SortPolicy::Height,
+			split: SplitPolicy::Horizontal,
+		}
+	}
+
+	/// Sets the order that rects are placed in.
+	pub fn sort(mut self, sort: SortPolicy) -> Self
+	{
+		self.sort = sort;
+		self
+	}
+
+	/// Sets how leaves are split after placing rects.
+	pub fn split(mut self, split: SplitPolicy) -> Self
+	{
+		self.split = split;
+		self
+	}
+
+	/// Returns the leaves of an empty bin with the given occupied regions cut out of it.
+	pub(crate) fn leaves_bin(bin: usize, width: u32, height: u32, occupied_list: &[Region]) -> Vec<Rectr>
+	{
+		let mut leaves = vec![Rectr::new(bin, 0, 0, width, height)];
+		for region in occupied_list
+		{
+			BinaryTreeGenerator::carve(&mut leaves, region);
+		}
+		leaves
+	}
+
+	/// Splits every leaf overlapping the given region into the disjoint leaves surrounding it.
+	fn carve(leaves: &mut Vec<Rectr>, region: &Region)
+	{
+		let mut leaf_index = 0;
+		while leaf_index < leaves.len()
+		{
+			let leaf = leaves[leaf_index];
+			if !leaf.region().intersects(region)
+			{
+				leaf_index += 1;
+				continue;
+			}
+			leaves.remove(leaf_index);
+
+			// Full height strips to the left and right, then what is left above and below.
+			let inner_x = ::std::cmp::max(leaf.x, region.x);
+			let inner_width = ::std::cmp::min(leaf.x + leaf.width, region.right()) - inner_x;
+			let piece_list =
+			[
+				Rectr::new(leaf.bin, leaf.x, leaf.y, region.x.saturating_sub(leaf.x), leaf.height),
+				Rectr::new(leaf.bin, region.right(), leaf.y, (leaf.x + leaf.width).saturating_sub(region.right()), leaf.height),
+				Rectr::new(leaf.bin, inner_x, leaf.y, inner_width, region.y.saturating_sub(leaf.y)),
+				Rectr::new(leaf.bin, inner_x, region.bottom(), inner_width, (leaf.y + leaf.height).saturating_sub(region.bottom())),
+			];
+			for piece in piece_list.iter().filter(|piece| !piece.empty())
+			{
+				leaves.insert(leaf_index, *piece);
+				leaf_index += 1;
+			}
+		}
+	}
+
+	/// Returns the orientation of the given orientations that fits the leaf best, if any fit.
+	///
+	/// The best fit leaves the least space along its shorter leftover side. Ties go to the earlier
+	/// orientation.
+	///
+	fn orientation_best(leaf: Rectr, orientation_list: &[(bool, Rect)]) -> Option<(bool, Rect)>
+	{
+		let mut best: Option<(u32, (bool, Rect))> = None;
+		for &(rotated, dimensions) in orientation_list
+		{
+			if dimensions.width > leaf.width || dimensions.height > leaf.height
+			{
+				continue;
+			}
+			let leftover = ::std::cmp::min(leaf.width - dimensions.width, leaf.height - dimensions.height);
+			if best.is_none_or(|(best_leftover, _)| leftover < best_leftover)
+			{
+				best = Some((leftover, (rotated, dimensions)));
+			}
+		}
+		best.map(|(_, orientation)| orientation)
+	}
+
+	/// Returns the first leaf that fits any of the given orientations along with its best orientation.
+	fn leaf_find(leaves: &[Rectr], orientation_list: &[(bool, Rect)]) -> Option<(usize, bool, Rect)>
+	{
+		leaves.iter().enumerate().filter_map(|(leaf_index, &leaf)|
+		{
+			BinaryTreeGenerator::orientation_best(leaf, orientation_list).map(|(rotated, dimensions)|
+			{
+				(leaf_index, rotated, dimensions)
+			})
+		}).next()
+	}
+
+	pub(crate) fn subdivide(leaves: &mut Vec<Rectr>, leaf_index: usize, width: u32, height: u32, split: SplitPolicy)
 	{
 		let leaf = leaves[leaf_index];
 
-		// Sub-divide so that bottom has smaller portion.
-		let leaf_left = Rectr::new(leaf.bin, leaf.x + width, leaf.y, leaf.width - width, height);
-		let leaf_bottom = Rectr::new(leaf.bin, leaf.x, leaf.y + height, leaf.width, leaf.height - height);
+		// The leaf that is split across keeps the full width or height of the original leaf.
+		let (leaf_left, leaf_bottom) = if split.horizontal(leaf, width, height)
+		{
+			(
+				Rectr::new(leaf.bin, leaf.x + width, leaf.y, leaf.width - width, height),
+				Rectr::new(leaf.bin, leaf.x, leaf.y + height, leaf.width, leaf.height - height),
+			)
+		}
+		else
+		{
+			(
+				Rectr::new(leaf.bin, leaf.x + width, leaf.y, leaf.width - width, leaf.height),
+				Rectr::new(leaf.bin, leaf.x, leaf.y + height, width, leaf.height - height),
+			)
+		};
 
 		if !leaf_left.empty() && !leaf_bottom.empty()
 		{
@@ -114,46 +242,69 @@ impl BinaryTreeGenerator
 	}
 }
 
+impl Default for BinaryTreeGenerator
+{
+	fn default() -> Self
+	{
+		BinaryTreeGenerator::new()
+	}
+}
+
 impl AtlasGenerator for BinaryTreeGenerator
 {
-	fn generate<T: AtlasRect>(&self, atlas: &mut Atlas<T>, widthr: u32, heightr: u32, rotate: bool)
+	fn generate<T: AtlasRect>(&self, atlas: &mut Atlas<T>, width: u32, height: u32, rotate: bool)
 	{
-		let atlas_list = sort_by_longest_width_increasing(atlas, rotate);
-		let mut atlas_index = 0;
+		let order_list = self.order_list(atlas, rotate);
+		self.generate_order(atlas, width, height, rotate, &order_list);
+	}
+}
+
+impl OrderedGenerator for BinaryTreeGenerator
+{
+	fn order_list<T: AtlasRect>(&self, atlas: &Atlas<T>, rotate: bool) -> Vec<(usize, bool)>
+	{
+		gen::sort_list(atlas, rotate, self.sort)
+	}
 
+	fn generate_order<T: AtlasRect>(&self, atlas: &mut Atlas<T>, widthr: u32, heightr: u32, rotate: bool, order_list: &[(usize, bool)])
+	{
 		// All we really care about are leaves of trees. Don't use an actual binary tree.
 		let mut leaves: Vec<Rectr> = Vec::with_capacity(2);
-		let mut max = 0;
-		while atlas_index != atlas_list.len()
+		for bin in 0..atlas.bin_list().len()
+		{
+			leaves.extend(BinaryTreeGenerator::leaves_bin(bin, widthr, heightr, &atlas.bin_occupied_list(bin)));
+		}
+
+		for &(rect_index, rotated) in order_list
 		{
-			let rect_index = atlas_list[atlas_index].index;
-			let mut inserted = false;
-			for leaf_index in 0..leaves.len()
+			// The given orientation is preferred, but the other is tried when rotations are allowed.
+			let mut orientation_list = vec![(rotated, atlas.rect_dimensions(rect_index, rotated))];
+			if rotate
 			{
-				let leaf = leaves[leaf_index];
-				let dimensions = &atlas_list[atlas_index].rect.rect;
-				if dimensions.width <= leaf.width && dimensions.height <= leaf.height
-				{
-					BinaryTreeGenerator::subdivide(&mut leaves, leaf_index, dimensions.width, dimensions.height);
-					atlas.bin_add_rect(leaf.bin, rect_index, leaf.x, leaf.y, atlas_list[atlas_index].rect.rotated);
+				orientation_list.push((!rotated, atlas.rect_dimensions(rect_index, !rotated)));
+			}
 
-					inserted = true;
-					break;
-				}
+			if let Some((leaf_index, rotated, dimensions)) = BinaryTreeGenerator::leaf_find(&leaves, &orientation_list)
+			{
+				let leaf = leaves[leaf_index];
+				BinaryTreeGenerator::subdivide(&mut leaves, leaf_index, dimensions.width, dimensions.height, self.split);
+				atlas.bin_add_rect(leaf.bin, rect_index, leaf.x, leaf.y, rotated);
 			}
-			if !inserted
+			else if atlas.bin_can_add()
 			{
-				let bin = atlas.bin_add_new(rect_index, false);
-
-				let leaf_index = leaves.len();
-				leaves.push(Rectr::new(bin, 0, 0, widthr, heightr));
+				let bin = atlas.bin_list().len();
+				let mut leaves_new = BinaryTreeGenerator::leaves_bin(bin, widthr, heightr, &atlas.bin_reserved_list(bin));
 
-				let dimensions = (&atlas.rect_list()[rect_index] as &AtlasRect).dimensions();
-
-				BinaryTreeGenerator::subdivide(&mut leaves, leaf_index, dimensions.width, dimensions.height);
+				// Rects that do not even fit in an empty bin are left unplaced.
+				if let Some((leaf_index, rotated, dimensions)) = BinaryTreeGenerator::leaf_find(&leaves_new, &orientation_list)
+				{
+					let leaf = leaves_new[leaf_index];
+					atlas.bin_add_empty();
+					atlas.bin_add_rect(bin, rect_index, leaf.x, leaf.y, rotated);
+					BinaryTreeGenerator::subdivide(&mut leaves_new, leaf_index, dimensions.width, dimensions.height, self.split);
+					leaves.extend(leaves_new);
+				}
 			}
-			max = ::std::cmp::max(max, leaves.len());
-			atlas_index += 1;
 		}
 	}
 }
@@ -1,21 +1,212 @@
 //! Bin packing algorithm generator implementations.
 
+mod annealing;
 mod binary_tree;
+mod optimal;
+mod registry;
+
+use std::cmp::{max, min, Ordering};
 
 use {Atlas, AtlasGenerator, AtlasRect};
+use util::{Rect, Region};
+
+pub use self::annealing::AnnealingGenerator;
+pub use self::binary_tree::{BinaryTreeGenerator, SplitPolicy};
+pub use self::optimal::OptimalGenerator;
+pub use self::registry::{GeneratorError, GeneratorParams, GeneratorRegistry};
+pub(crate) use self::binary_tree::Rectr;
+
+/// The order that greedy generators place rects in.
+///
+/// Every policy sorts in descending order, breaking ties with a second property and then ascending
+/// rect index. Sizes include padding and alignment and, when rotations are allowed, are taken after
+/// rotating rects to be at least as wide as they are tall.
+///
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum SortPolicy
+{
+	/// By height, then width.
+	#[default]
+	Height,
+
+	/// By width, then height.
+	Width,
+
+	/// By area, then longest side.
+	Area,
+
+	/// By perimeter, then longest side.
+	Perimeter,
+
+	/// By longest side, then shortest side.
+	MaxSide,
+
+	/// By ratio of longest to shortest side, then area.
+	SideRatio,
+
+	/// In the order of the rect list.
+	None,
+}
+
+impl SortPolicy
+{
+	/// Compares two sizes so that the one to place first is less.
+	fn compare(&self, left: Rect, right: Rect) -> Ordering
+	{
+		let area = |rect: Rect| rect.width as u64 * rect.height as u64;
+		let long = |rect: Rect| max(rect.width, rect.height) as u64;
+		let short = |rect: Rect| min(rect.width, rect.height) as u64;
+		match *self
+		{
+			SortPolicy::Height => (right.height, right.width).cmp(&(left.height, left.width)),
+			SortPolicy::Width => (right.width, right.height).cmp(&(left.width, left.height)),
+			SortPolicy::Area => (area(right), long(right)).cmp(&(area(left), long(left))),
+			SortPolicy::Perimeter =>
+			{
+				let perimeter = |rect: Rect| rect.width as u64 + rect.height as u64;
+				(perimeter(right), long(right)).cmp(&(perimeter(left), long(left)))
+			}
+			SortPolicy::MaxSide => (long(right), short(right)).cmp(&(long(left), short(left))),
+			SortPolicy::SideRatio =>
+			{
+				// Ratios are compared by cross multiplying to avoid floating point.
+				(long(right) * short(left)).cmp(&(long(left) * short(right))).then(area(right).cmp(&area(left)))
+			}
+			SortPolicy::None => Ordering::Equal,
+		}
+	}
+}
+
+/// Returns the unplaced rects in the order of the given policy, along with whether each is rotated.
+pub(crate) fn sort_list<T: AtlasRect>(atlas: &Atlas<T>, rotate: bool, sort: SortPolicy) -> Vec<(usize, bool)>
+{
+	let placed_list = atlas.rect_placed_list();
+	let mut rect_list: Vec<(usize, bool, Rect)> = Vec::with_capacity(atlas.rect_list().len());
+	for (index, rect) in atlas.rect_list().iter().enumerate()
+	{
+		if placed_list[index]
+		{
+			continue;
+		}
+		let rotated = (rect as &dyn AtlasRect).dimensions_longest_rotated(rotate).rotated;
+		rect_list.push((index, rotated, atlas.rect_dimensions(index, rotated)));
+	}
+
+	// Equal rects are ordered by index so that output never depends on the sort.
+	rect_list.sort_by(|left, right| sort.compare(left.2, right.2).then(left.0.cmp(&right.0)));
+	rect_list.iter().map(|&(index, rotated, _)| (index, rotated)).collect()
+}
+
+/// Returns the top-most, then left-most position where the given dimensions do not overlap any of
+/// the occupied regions.
+fn position_free(occupied_list: &[Region], dimensions: Rect) -> (u32, u32)
+{
+	let mut candidate_list = vec![(0, 0)];
+	for region in occupied_list
+	{
+		candidate_list.push((region.right(), region.y));
+		candidate_list.push((region.x, region.bottom()));
+		candidate_list.push((0, region.bottom()));
+	}
+	candidate_list.sort_by_key(|&(x, y)| (y, x));
 
-pub use self::binary_tree::BinaryTreeGenerator;
+	for (x, y) in candidate_list
+	{
+		let candidate = Region::new(x, y, dimensions.width, dimensions.height);
+		if !occupied_list.iter().any(|region| region.intersects(&candidate))
+		{
+			return (x, y);
+		}
+	}
+	unreachable!("Position below all occupied regions is always free");
+}
+
+/// A generator whose result depends on the order that rects are placed in.
+///
+/// Meta-generators such as `AnnealingGenerator` search over orders and rotations of rects, placing
+/// them with `generate_order`. Generating with the order from `order_list` must give the same
+/// result as `AtlasGenerator::generate`.
+///
+pub trait OrderedGenerator: AtlasGenerator
+{
+	/// Returns the unplaced rects in the order they are placed in, along with whether each is rotated.
+	fn order_list<T: AtlasRect>(&self, atlas: &Atlas<T>, rotate: bool) -> Vec<(usize, bool)>;
+
+	/// Places the given rects in order, rotating each as indicated.
+	///
+	/// When rotations are allowed, generators may still rotate rects the other way if that fits
+	/// better.
+	///
+	fn generate_order<T: AtlasRect>(&self, atlas: &mut Atlas<T>, width: u32, height: u32, rotate: bool, order_list: &[(usize, bool)]);
+}
+
+/// A generator that can be used as a trait object, for instance to choose a generator at runtime.
+///
+/// `AtlasGenerator` is generic over rects, so it is not object safe. Every `AtlasGenerator`
+/// implements this trait, which only generates atlases of plain rects. In turn,
+/// `Box<dyn DynGenerator>` implements `AtlasGenerator` by generating an atlas of the sizes of the
+/// rects.
+///
+/// Boxed generators can be shared between threads, so that they can be used with
+/// `AtlasBuilder::par_generate_best`.
+///
+pub trait DynGenerator: Send + Sync
+{
+	/// Generates a list of bins for the given atlas of rects.
+	fn generate_rects(&self, atlas: &mut Atlas<Rect>, width: u32, height: u32, rotate: bool);
+}
+
+impl<G: AtlasGenerator + Send + Sync> DynGenerator for G
+{
+	fn generate_rects(&self, atlas: &mut Atlas<Rect>, width: u32, height: u32, rotate: bool)
+	{
+		self.generate(atlas, width, height, rotate);
+	}
+}
+
+impl AtlasGenerator for Box<dyn DynGenerator>
+{
+	fn generate<T: AtlasRect>(&self, atlas: &mut Atlas<T>, width: u32, height: u32, rotate: bool)
+	{
+		let rect_list: Vec<Rect> = atlas.rect_list().iter().map(|rect| Rect::new(rect.width(), rect.height())).collect();
+		let mut rect_atlas = atlas.with_rect_list(&rect_list);
+		(**self).generate_rects(&mut rect_atlas, width, height, rotate);
+		atlas.bin_list = rect_atlas.bin_list;
+	}
+}
 
 /// A generator that creates a separate bin for each object.
 pub struct PassthroughGenerator;
 
 impl AtlasGenerator for PassthroughGenerator
 {
-	fn generate<T: AtlasRect>(&self, atlas: &mut Atlas<T>, _: u32, _: u32, _: bool)
+	fn generate<T: AtlasRect>(&self, atlas: &mut Atlas<T>, width: u32, height: u32, rotate: bool)
+	{
+		let order_list = self.order_list(atlas, rotate);
+		self.generate_order(atlas, width, height, rotate, &order_list);
+	}
+}
+
+impl OrderedGenerator for PassthroughGenerator
+{
+	fn order_list<T: AtlasRect>(&self, atlas: &Atlas<T>, _: bool) -> Vec<(usize, bool)>
+	{
+		let placed_list = atlas.rect_placed_list();
+		(0..atlas.rect_list().len()).filter(|&rect_index| !placed_list[rect_index]).map(|rect_index| (rect_index, false)).collect()
+	}
+
+	fn generate_order<T: AtlasRect>(&self, atlas: &mut Atlas<T>, _: u32, _: u32, _: bool, order_list: &[(usize, bool)])
 	{
-		for rect_index in 0..atlas.rect_list().len()
+		for &(rect_index, rotate) in order_list
 		{
-			atlas.bin_add_new(rect_index, false);
+			if !atlas.bin_can_add()
+			{
+				break;
+			}
+			let bin_index = atlas.bin_add_empty();
+			let dimensions = atlas.rect_dimensions(rect_index, rotate);
+			let (x, y) = position_free(&atlas.bin_occupied_list(bin_index), dimensions);
+			atlas.bin_add_rect(bin_index, rect_index, x, y, rotate);
 		}
 	}
 }
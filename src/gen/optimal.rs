@@ -0,0 +1,536 @@
+use std::cmp::{max, Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::time::{Duration, Instant};
+
+use {Atlas, AtlasGenerator, AtlasRect};
+use gen::BinaryTreeGenerator;
+use util::{self, Rect, Region};
+
+/// An orientation an unplaced rect may be placed in.
+#[derive(Clone, Copy)]
+struct Orientation
+{
+	dimensions: Rect,
+	rotate: bool,
+}
+
+/// An unplaced rect along with the orientations it may be placed in.
+struct Item
+{
+	rect_index: usize,
+	orientation_list: Vec<Orientation>,
+	area: u64,
+	same_as_previous: bool,
+}
+
+/// A placement of an item as its bin, position and rotation.
+#[derive(Clone, Copy)]
+struct Placement
+{
+	bin: usize,
+	x: u32,
+	y: u32,
+	rotate: bool,
+}
+
+/// Counts searched nodes and stops the search once the node or time limit is reached.
+struct Budget
+{
+	node_count: u64,
+	node_limit: u64,
+	deadline: Option<Instant>,
+	exhausted: bool,
+}
+
+impl Budget
+{
+	/// Spends a node and returns false if the budget has run out.
+	fn spend(&mut self) -> bool
+	{
+		self.node_count += 1;
+		if self.node_count > self.node_limit
+		{
+			self.exhausted = true;
+		}
+		else if self.node_count.is_multiple_of(1024)
+		{
+			self.exhausted = self.deadline.is_some_and(|deadline| Instant::now() >= deadline);
+		}
+		!self.exhausted
+	}
+}
+
+/// A depth first search for a packing of every item into a limited number of bins.
+///
+/// Rects can always be pushed left and up until they touch either the edge of the bin, an occupied
+/// region or another rect. Positions are therefore limited to the edges of occupied regions plus
+/// any sum of rect dimensions, which keeps the search complete regardless of the order that items
+/// are placed in.
+///
+struct Search<'s>
+{
+	item_list: &'s [Item],
+	width: u32,
+	height: u32,
+	x_list: &'s [u32],
+	y_list: &'s [u32],
+	bin_list: Vec<Vec<Region>>,
+	bin_used_list: Vec<usize>,
+	bin_free_list: Vec<u64>,
+	symmetric_start: usize,
+	free_area: u64,
+	placement_list: Vec<Placement>,
+	budget: &'s mut Budget,
+}
+
+impl<'s> Search<'s>
+{
+	/// Returns true if the remaining items were placed, with their placements in `placement_list`.
+	fn search(&mut self, item_index: usize, remaining_area: u64) -> bool
+	{
+		let item_list = self.item_list;
+		if item_index == item_list.len()
+		{
+			return true;
+		}
+		if remaining_area > self.free_area || !self.budget.spend()
+		{
+			return false;
+		}
+		let item = &item_list[item_index];
+
+		// Identical rects are placed in increasing order so that swapping them is not searched again.
+		let minimum = if item.same_as_previous
+		{
+			let previous = self.placement_list[item_index - 1];
+			Some((previous.bin, previous.y, previous.x))
+		}
+		else
+		{
+			None
+		};
+
+		for bin in 0..self.bin_list.len()
+		{
+			// Empty bins that are alike are opened in order.
+			if bin > self.symmetric_start && self.bin_used_list[bin - 1] == 0
+			{
+				break;
+			}
+			if item.area > self.bin_free_list[bin]
+			{
+				continue;
+			}
+			for orientation in &item.orientation_list
+			{
+				if self.place(bin, item_index, *orientation, minimum, remaining_area)
+				{
+					return true;
+				}
+				if self.budget.exhausted
+				{
+					return false;
+				}
+			}
+		}
+		false
+	}
+
+	/// Tries every position of an item in the given bin and orientation.
+	fn place(&mut self, bin: usize, item_index: usize, orientation: Orientation, minimum: Option<(usize, u32, u32)>,
+		remaining_area: u64) -> bool
+	{
+		let (x_list, y_list) = (self.x_list, self.y_list);
+		let dimensions = orientation.dimensions;
+		if dimensions.width > self.width || dimensions.height > self.height
+		{
+			return false;
+		}
+		let area = self.item_list[item_index].area;
+		let (width, height) = (self.width, self.height);
+		for &y in y_list.iter().take_while(|&&y| y + dimensions.height <= height)
+		{
+			let mut x_index = 0;
+			while x_index < x_list.len() && x_list[x_index] + dimensions.width <= width
+			{
+				let x = x_list[x_index];
+				if minimum.is_some_and(|minimum| (bin, y, x) <= minimum)
+				{
+					x_index += 1;
+					continue;
+				}
+				let candidate = Region::new(x, y, dimensions.width, dimensions.height);
+				let overlap = self.bin_list[bin].iter().find(|region| region.intersects(&candidate)).map(|region| region.right());
+				if let Some(right) = overlap
+				{
+					// Skip every position still overlapping the region.
+					x_index += x_list[x_index..].iter().take_while(|&&x| x < right).count();
+					continue;
+				}
+
+				self.bin_list[bin].push(candidate);
+				self.bin_used_list[bin] += 1;
+				self.bin_free_list[bin] -= area;
+				self.free_area -= area;
+				self.placement_list.push(Placement
+				{
+					bin,
+					x,
+					y,
+					rotate: orientation.rotate,
+				});
+				if self.search(item_index + 1, remaining_area - area)
+				{
+					return true;
+				}
+				self.placement_list.pop();
+				self.free_area += area;
+				self.bin_free_list[bin] += area;
+				self.bin_used_list[bin] -= 1;
+				self.bin_list[bin].pop();
+				if self.budget.exhausted
+				{
+					return false;
+				}
+				x_index += 1;
+			}
+		}
+		false
+	}
+}
+
+/// Returns the area of the union of the given regions that lies within the given dimensions.
+fn region_union_area(dimensions: Rect, region_list: &[Region]) -> u64
+{
+	let mut x_list: Vec<u32> = region_list.iter().flat_map(|region| vec![region.x, region.right()]).map(|x| x.min(dimensions.width)).collect();
+	let mut y_list: Vec<u32> = region_list.iter().flat_map(|region| vec![region.y, region.bottom()]).map(|y| y.min(dimensions.height)).collect();
+	x_list.sort();
+	x_list.dedup();
+	y_list.sort();
+	y_list.dedup();
+	let mut area = 0;
+	for x_pair in x_list.windows(2)
+	{
+		for y_pair in y_list.windows(2)
+		{
+			let cell = Region::new(x_pair[0], y_pair[0], x_pair[1] - x_pair[0], y_pair[1] - y_pair[0]);
+			if region_list.iter().any(|region| region.intersects(&cell))
+			{
+				area += cell.width as u64 * cell.height as u64;
+			}
+		}
+	}
+	area
+}
+
+/// Returns every origin plus every sum of item dimensions, up to the given limit.
+fn position_list(origin_list: &[u32], item_list: &[Item], limit: u32, horizontal: bool) -> Vec<u32>
+{
+	let mut sum_list = vec![false; limit as usize + 1];
+	sum_list[0] = true;
+	for item in item_list
+	{
+		let previous_list = sum_list.clone();
+		for orientation in &item.orientation_list
+		{
+			let size = if horizontal { orientation.dimensions.width } else { orientation.dimensions.height } as usize;
+			for sum in (0..previous_list.len()).filter(|&sum| previous_list[sum] && sum + size < previous_list.len())
+			{
+				sum_list[sum + size] = true;
+			}
+		}
+	}
+	let mut position_list = Vec::new();
+	for &origin in origin_list.iter().filter(|&&origin| origin <= limit)
+	{
+		position_list.extend((0..(limit - origin) as usize + 1).filter(|&sum| sum_list[sum]).map(|sum| origin + sum as u32));
+	}
+	position_list.sort();
+	position_list.dedup();
+	position_list
+}
+
+/// A generator that searches for the fewest number of bins possible.
+///
+/// The search is a branch-and-bound over the positions of every rect, starting from the result of
+/// `BinaryTreeGenerator` and stopping once the lower bound of bins is reached. When everything fits
+/// in a single bin, smaller bins are then searched for the smallest area possible.
+///
+/// The search grows exponentially with the number of rects, so it is only suited to small atlases
+/// of a few dozen rects. It stops after a number of searched nodes or, optionally, after some time.
+/// The best result found so far is used when the search stops, which is never worse than the result
+/// of `BinaryTreeGenerator`. Only the node limit is deterministic, so a time limit may generate
+/// different bins across runs.
+///
+/// Candidate positions are the sums of rect sizes, found with a list of `width + 1` flags for every
+/// rect, and again for the height, so bins much larger than the rects make the search slower to set
+/// up as well.
+///
+pub struct OptimalGenerator
+{
+	node_limit: u64,
+	time_limit: Option<Duration>,
+}
+
+impl OptimalGenerator
+{
+	/// Creates a generator searching at most a hundred thousand nodes, without a time limit.
+	pub fn new() -> Self
+	{
+		OptimalGenerator
+		{
+			node_limit: 100_000,
+			time_limit: None,
+		}
+	}
+
+	/// Sets the maximal number of nodes searched.
+	pub fn node_limit(mut self, node_limit: u64) -> Self
+	{
+		self.node_limit = node_limit;
+		self
+	}
+
+	/// Sets the maximal time spent searching.
+	///
+	/// The time is only checked every 1024 nodes, so the search may run past the limit by as long as
+	/// searching that many nodes takes.
+	///
+	pub fn time_limit(mut self, time_limit: Duration) -> Self
+	{
+		self.time_limit = Some(time_limit);
+		self
+	}
+
+	/// Returns the unplaced rects with the largest first.
+	fn item_list<T: AtlasRect>(atlas: &Atlas<T>, rotate: bool) -> Vec<Item>
+	{
+		let placed_list = atlas.rect_placed_list();
+		let mut item_list: Vec<Item> = (0..atlas.rect_list().len()).filter(|&rect_index| !placed_list[rect_index]).map(|rect_index|
+		{
+			let dimensions = atlas.rect_dimensions(rect_index, false);
+			let mut orientation_list = vec![Orientation { dimensions, rotate: false }];
+			let rotated = atlas.rect_dimensions(rect_index, true);
+			if rotate && (rotated.width, rotated.height) != (dimensions.width, dimensions.height)
+			{
+				orientation_list.push(Orientation { dimensions: rotated, rotate: true });
+			}
+			Item
+			{
+				rect_index,
+				orientation_list,
+				area: dimensions.width as u64 * dimensions.height as u64,
+				same_as_previous: false,
+			}
+		}).collect();
+
+		let key = |item: &Item|
+		{
+			let dimensions = item.orientation_list[0].dimensions;
+			(item.area, max(dimensions.width, dimensions.height), dimensions.width)
+		};
+		item_list.sort_by(|left, right|
+		{
+			match key(right).cmp(&key(left))
+			{
+				Ordering::Equal => left.rect_index.cmp(&right.rect_index),
+				ordering => ordering,
+			}
+		});
+		for item_index in 1..item_list.len()
+		{
+			item_list[item_index].same_as_previous = key(&item_list[item_index]) == key(&item_list[item_index - 1]) &&
+				item_list[item_index].orientation_list.len() == item_list[item_index - 1].orientation_list.len();
+		}
+		item_list
+	}
+
+	/// Returns the regions occupied in the indicated bin, even if the bin does not exist yet.
+	fn occupied_list<T: AtlasRect>(atlas: &Atlas<T>, bin_index: usize) -> Vec<Region>
+	{
+		if bin_index < atlas.bin_list().len()
+		{
+			atlas.bin_occupied_list(bin_index)
+		}
+		else
+		{
+			atlas.bin_reserved_list(bin_index)
+		}
+	}
+
+	/// Searches for a packing of every item within the given bins and dimensions.
+	fn search<T: AtlasRect>(atlas: &Atlas<T>, item_list: &[Item], width: u32, height: u32, bin_limit: usize,
+		position_pair: &(Vec<u32>, Vec<u32>), budget: &mut Budget) -> Option<Vec<Placement>>
+	{
+		let x_list = &position_pair.0[..position_pair.0.iter().take_while(|&&x| x < width).count()];
+		let y_list = &position_pair.1[..position_pair.1.iter().take_while(|&&y| y < height).count()];
+
+		let dimensions = Rect::new(width, height);
+		let bin_list: Vec<Vec<Region>> = (0..bin_limit).map(|bin_index| OptimalGenerator::occupied_list(atlas, bin_index)).collect();
+		let bin_free_list: Vec<u64> = bin_list.iter()
+			.map(|region_list| (&dimensions as &dyn AtlasRect).area() - region_union_area(dimensions, region_list))
+			.collect();
+
+		// Bins past any existing bin or bin specific reservation are alike.
+		let shared_count = atlas.bin_reserved_list(usize::MAX).len();
+		let symmetric_start = (atlas.bin_list().len()..bin_limit)
+			.rfind(|&bin_index| atlas.bin_reserved_list(bin_index).len() != shared_count)
+			.map_or(atlas.bin_list().len(), |bin_index| bin_index + 1);
+
+		let mut search = Search
+		{
+			item_list,
+			width,
+			height,
+			x_list,
+			y_list,
+			bin_list,
+			bin_used_list: vec![0; bin_limit],
+			free_area: bin_free_list.iter().sum(),
+			bin_free_list,
+			symmetric_start,
+			placement_list: Vec::with_capacity(item_list.len()),
+			budget,
+		};
+		let remaining_area = item_list.iter().map(|item| item.area).sum();
+		if search.search(0, remaining_area)
+		{
+			Some(search.placement_list)
+		}
+		else
+		{
+			None
+		}
+	}
+
+	/// Returns a copy of the atlas with every item placed.
+	fn apply<'a, T: AtlasRect>(atlas: &Atlas<'a, T>, item_list: &[Item], placement_list: &[Placement]) -> Atlas<'a, T>
+	{
+		let mut result = atlas.clone();
+		for (item, placement) in item_list.iter().zip(placement_list)
+		{
+			while result.bin_list().len() <= placement.bin
+			{
+				result.bin_add_empty();
+			}
+			result.bin_add_rect(placement.bin, item.rect_index, placement.x, placement.y, placement.rotate);
+		}
+		result
+	}
+}
+
+impl Default for OptimalGenerator
+{
+	fn default() -> Self
+	{
+		OptimalGenerator::new()
+	}
+}
+
+impl AtlasGenerator for OptimalGenerator
+{
+	fn generate<T: AtlasRect>(&self, atlas: &mut Atlas<T>, width: u32, height: u32, rotate: bool)
+	{
+		let mut best = atlas.clone();
+		BinaryTreeGenerator::new().generate(&mut best, width, height, rotate);
+		let item_list = OptimalGenerator::item_list(atlas, rotate);
+		if item_list.is_empty() || width == 0 || height == 0
+		{
+			*atlas = best;
+			return;
+		}
+
+		let mut budget = Budget
+		{
+			node_count: 0,
+			node_limit: self.node_limit,
+			deadline: self.time_limit.map(|time_limit| Instant::now() + time_limit),
+			exhausted: false,
+		};
+		let existing_count = atlas.bin_list().len();
+		let complete = best.rect_placed_list().iter().all(|&placed| placed);
+		let mut bin_limit = if complete
+		{
+			best.bin_list().len() - 1
+		}
+		else
+		{
+			atlas.max_bins().unwrap_or(existing_count + item_list.len())
+		};
+
+		let dimension_list: Vec<Rect> = (0..atlas.rect_list().len()).map(|rect_index| atlas.rect_dimensions(rect_index, false)).collect();
+		let lower_bound = max(max(existing_count, 1), util::lower_bound(&dimension_list, Rect::new(width, height), rotate));
+		let occupied_list: Vec<Region> = (0..bin_limit + 1).flat_map(|bin_index| OptimalGenerator::occupied_list(atlas, bin_index)).collect();
+		let mut origin_list = (vec![0], vec![0]);
+		origin_list.0.extend(occupied_list.iter().map(|region| region.right()));
+		origin_list.1.extend(occupied_list.iter().map(|region| region.bottom()));
+		let position_pair = (position_list(&origin_list.0, &item_list, width, true), position_list(&origin_list.1, &item_list, height, false));
+
+		// Search for fewer bins until the lower bound is reached or the search fails.
+		let mut found = false;
+		while bin_limit >= lower_bound
+		{
+			match OptimalGenerator::search(atlas, &item_list, width, height, bin_limit, &position_pair, &mut budget)
+			{
+				Some(placement_list) =>
+				{
+					best = OptimalGenerator::apply(atlas, &item_list, &placement_list);
+					bin_limit = placement_list.iter().map(|placement| placement.bin + 1).max().unwrap_or(0) - 1;
+					found = true;
+				}
+				None => break,
+			}
+		}
+		if !complete && !found
+		{
+			*atlas = best;
+			return;
+		}
+
+		// Search for a smaller single bin, by increasing area.
+		if best.bin_list().len() == 1 && !budget.exhausted
+		{
+			let best_area = (&best.bin_list()[0] as &dyn AtlasRect).area();
+			let occupied_list = OptimalGenerator::occupied_list(atlas, 0);
+			let min_width = occupied_list.iter().map(|region| region.right()).max().unwrap_or(0);
+			let min_height = occupied_list.iter().map(|region| region.bottom()).max().unwrap_or(0);
+			let min_area = item_list.iter().map(|item| item.area).sum::<u64>() + region_union_area(Rect::new(width, height), &occupied_list);
+			let width_list = &position_pair.0;
+			let height_list: Vec<u32> = position_pair.1.iter().cloned().filter(|&bin_height| bin_height >= min_height && bin_height != 0).collect();
+
+			// Every width starts at its smallest height and moves to the next height once searched.
+			let mut size_heap = BinaryHeap::new();
+			let size_next = |size_heap: &mut BinaryHeap<Reverse<(u64, u32, u32, usize)>>, bin_width: u32, height_index: usize|
+			{
+				if let Some(&bin_height) = height_list.get(height_index)
+				{
+					let area = bin_width as u64 * bin_height as u64;
+					if area < best_area
+					{
+						size_heap.push(Reverse((area, bin_height, bin_width, height_index)));
+					}
+				}
+			};
+			for &bin_width in width_list.iter().filter(|&&bin_width| bin_width >= min_width && bin_width != 0)
+			{
+				let bin_height = util::div_ceil(min_area, bin_width as u64);
+				let height_index = height_list.iter().take_while(|&&height| (height as u64) < bin_height).count();
+				size_next(&mut size_heap, bin_width, height_index);
+			}
+			while let Some(Reverse((_, bin_height, bin_width, height_index))) = size_heap.pop()
+			{
+				if let Some(placement_list) = OptimalGenerator::search(atlas, &item_list, bin_width, bin_height, 1, &position_pair, &mut budget)
+				{
+					best = OptimalGenerator::apply(atlas, &item_list, &placement_list);
+					break;
+				}
+				if budget.exhausted
+				{
+					break;
+				}
+				size_next(&mut size_heap, bin_width, height_index + 1);
+			}
+		}
+		*atlas = best;
+	}
+}
@@ -0,0 +1,274 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use gen::{AnnealingGenerator, BinaryTreeGenerator, DynGenerator, OptimalGenerator, PassthroughGenerator, SortPolicy,
+	SplitPolicy};
+
+/// An error indicating that a generator could not be created from a registry.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GeneratorError
+{
+	/// No generator is registered under the given name.
+	UnknownGenerator(String),
+
+	/// The generator does not take a parameter with the given name.
+	UnknownParam(String),
+
+	/// The parameter with the given name has a value that could not be parsed.
+	InvalidParam(String, String),
+
+	/// The given text is not a generator description.
+	Syntax(String),
+}
+
+impl fmt::Display for GeneratorError
+{
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+	{
+		match *self
+		{
+			GeneratorError::UnknownGenerator(ref name) => write!(f, "unknown generator `{}`", name),
+			GeneratorError::UnknownParam(ref key) => write!(f, "unknown parameter `{}`", key),
+			GeneratorError::InvalidParam(ref key, ref value) => write!(f, "invalid value `{}` for parameter `{}`", value, key),
+			GeneratorError::Syntax(ref text) => write!(f, "malformed generator description `{}`", text),
+		}
+	}
+}
+
+impl Error for GeneratorError {}
+
+/// Named parameters used to create a generator, with values kept as text until read.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GeneratorParams
+{
+	param_map: BTreeMap<String, String>,
+}
+
+impl GeneratorParams
+{
+	/// Creates an empty list of parameters.
+	pub fn new() -> Self
+	{
+		GeneratorParams
+		{
+			param_map: BTreeMap::new(),
+		}
+	}
+
+	/// Parses parameters in the form `key=value,key=value`.
+	pub fn parse(text: &str) -> Result<Self, GeneratorError>
+	{
+		let mut params = GeneratorParams::new();
+		for pair in text.split(',').map(str::trim).filter(|pair| !pair.is_empty())
+		{
+			let mut split = pair.splitn(2, '=');
+			match (split.next().map(str::trim), split.next().map(str::trim))
+			{
+				(Some(key), Some(value)) if !key.is_empty() => params = params.set(key, value),
+				_ => return Err(GeneratorError::Syntax(text.to_string())),
+			}
+		}
+		Ok(params)
+	}
+
+	/// Sets the parameter with the given name.
+	pub fn set(mut self, key: &str, value: &str) -> Self
+	{
+		self.param_map.insert(key.to_string(), value.to_string());
+		self
+	}
+
+	/// Returns the text of the parameter with the given name, if set.
+	pub fn get(&self, key: &str) -> Option<&str>
+	{
+		self.param_map.get(key).map(String::as_str)
+	}
+
+	/// Returns the parsed value of the parameter with the given name, if set.
+	pub fn value<V: FromStr>(&self, key: &str) -> Result<Option<V>, GeneratorError>
+	{
+		self.value_with(key, |value| value.parse().ok())
+	}
+
+	/// Returns the value of the parameter with the given name parsed by the given function, if set.
+	pub fn value_with<V, F: Fn(&str) -> Option<V>>(&self, key: &str, parse: F) -> Result<Option<V>, GeneratorError>
+	{
+		match self.get(key)
+		{
+			Some(value) => parse(value).map(Some).ok_or_else(|| GeneratorError::InvalidParam(key.to_string(), value.to_string())),
+			None => Ok(None),
+		}
+	}
+
+	/// Returns an error if any parameter is not one of the given names.
+	pub fn check(&self, key_list: &[&str]) -> Result<(), GeneratorError>
+	{
+		match self.param_map.keys().find(|key| !key_list.contains(&key.as_str()))
+		{
+			Some(key) => Err(GeneratorError::UnknownParam(key.clone())),
+			None => Ok(()),
+		}
+	}
+}
+
+/// Creates a generator from parameters.
+type Factory = Box<dyn Fn(&GeneratorParams) -> Result<Box<dyn DynGenerator>, GeneratorError>>;
+
+fn sort_policy(value: &str) -> Option<SortPolicy>
+{
+	match value
+	{
+		"height" => Some(SortPolicy::Height),
+		"width" => Some(SortPolicy::Width),
+		"area" => Some(SortPolicy::Area),
+		"perimeter" => Some(SortPolicy::Perimeter),
+		"max_side" => Some(SortPolicy::MaxSide),
+		"side_ratio" => Some(SortPolicy::SideRatio),
+		"none" => Some(SortPolicy::None),
+		_ => None,
+	}
+}
+
+fn split_policy(value: &str) -> Option<SplitPolicy>
+{
+	match value
+	{
+		"horizontal" => Some(SplitPolicy::Horizontal),
+		"vertical" => Some(SplitPolicy::Vertical),
+		"shorter_leftover_axis" => Some(SplitPolicy::ShorterLeftoverAxis),
+		"longer_leftover_axis" => Some(SplitPolicy::LongerLeftoverAxis),
+		"minimize_area" => Some(SplitPolicy::MinimizeArea),
+		"maximize_area" => Some(SplitPolicy::MaximizeArea),
+		_ => None,
+	}
+}
+
+/// Creates a binary tree generator from the `sort` and `split` parameters.
+fn binary_tree(params: &GeneratorParams) -> Result<BinaryTreeGenerator, GeneratorError>
+{
+	let mut generator = BinaryTreeGenerator::new();
+	if let Some(sort) = params.value_with("sort", sort_policy)?
+	{
+		generator = generator.sort(sort);
+	}
+	if let Some(split) = params.value_with("split", split_policy)?
+	{
+		generator = generator.split(split);
+	}
+	Ok(generator)
+}
+
+/// Maps names and parameters to generators, so that generators can be chosen at runtime.
+///
+/// The built-in generators are registered as follows, with optional parameters:
+///
+/// - `passthrough`
+/// - `binary_tree`: `sort` and `split`, named like the variants of `SortPolicy` and `SplitPolicy`
+///   in snake case, such as `max_side` or `shorter_leftover_axis`.
+/// - `optimal`: `node_limit` and `time_limit` in milliseconds.
+/// - `annealing`: `seed`, `iteration_limit` and the parameters of the inner `binary_tree`.
+///
+pub struct GeneratorRegistry
+{
+	factory_map: BTreeMap<String, Factory>,
+}
+
+impl GeneratorRegistry
+{
+	/// Creates a registry of the built-in generators.
+	pub fn new() -> Self
+	{
+		GeneratorRegistry::empty()
+			.register("passthrough", |params|
+			{
+				params.check(&[])?;
+				Ok(Box::new(PassthroughGenerator))
+			})
+			.register("binary_tree", |params|
+			{
+				params.check(&["sort", "split"])?;
+				Ok(Box::new(binary_tree(params)?))
+			})
+			.register("optimal", |params|
+			{
+				params.check(&["node_limit", "time_limit"])?;
+				let mut generator = OptimalGenerator::new();
+				if let Some(node_limit) = params.value("node_limit")?
+				{
+					generator = generator.node_limit(node_limit);
+				}
+				if let Some(time_limit) = params.value("time_limit")?
+				{
+					generator = generator.time_limit(Duration::from_millis(time_limit));
+				}
+				Ok(Box::new(generator))
+			})
+			.register("annealing", |params|
+			{
+				params.check(&["seed", "iteration_limit", "sort", "split"])?;
+				let mut generator = AnnealingGenerator::new(binary_tree(params)?);
+				if let Some(seed) = params.value("seed")?
+				{
+					generator = generator.seed(seed);
+				}
+				if let Some(iteration_limit) = params.value("iteration_limit")?
+				{
+					generator = generator.iteration_limit(iteration_limit);
+				}
+				Ok(Box::new(generator))
+			})
+	}
+
+	/// Creates a registry without any generators.
+	pub fn empty() -> Self
+	{
+		GeneratorRegistry
+		{
+			factory_map: BTreeMap::new(),
+		}
+	}
+
+	/// Registers a generator under the given name, replacing any generator of the same name.
+	pub fn register<F>(mut self, name: &str, factory: F) -> Self
+		where F: Fn(&GeneratorParams) -> Result<Box<dyn DynGenerator>, GeneratorError> + 'static
+	{
+		self.factory_map.insert(name.to_string(), Box::new(factory));
+		self
+	}
+
+	/// Returns the names of the registered generators in alphabetical order.
+	pub fn name_list(&self) -> Vec<&str>
+	{
+		self.factory_map.keys().map(String::as_str).collect()
+	}
+
+	/// Creates the generator registered under the given name with the given parameters.
+	pub fn create(&self, name: &str, params: &GeneratorParams) -> Result<Box<dyn DynGenerator>, GeneratorError>
+	{
+		match self.factory_map.get(name)
+		{
+			Some(factory) => factory(params),
+			None => Err(GeneratorError::UnknownGenerator(name.to_string())),
+		}
+	}
+
+	/// Creates a generator from a description in the form `name` or `name:key=value,key=value`.
+	pub fn parse(&self, text: &str) -> Result<Box<dyn DynGenerator>, GeneratorError>
+	{
+		let mut split = text.splitn(2, ':');
+		let name = split.next().unwrap_or("").trim();
+		let params = GeneratorParams::parse(split.next().unwrap_or(""))?;
+		self.create(name, &params)
+	}
+}
+
+impl Default for GeneratorRegistry
+{
+	fn default() -> Self
+	{
+		GeneratorRegistry::new()
+	}
+}
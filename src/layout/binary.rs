@@ -0,0 +1,366 @@
+use std::error::Error;
+use std::fmt;
+use std::str;
+
+use {Atlas, AtlasRect};
+use layout::Trim;
+use util::Rect;
+
+/// The bytes that every binary layout starts with.
+pub const MAGIC: [u8; 4] = *b"TXAT";
+
+/// The version of the binary layout format.
+pub const VERSION: u16 = 1;
+
+/// The size of the header: magic, version, reserved, bin count, region count and string table size.
+const HEADER_SIZE: usize = 20;
+
+/// The size of a bin record: width and height.
+const BIN_SIZE: usize = 8;
+
+/// The size of a region record: name offset and length, rect index, bin, x, y, width, height, flags,
+/// trim x and y, and source width and height.
+const REGION_SIZE: usize = 52;
+
+/// The region flag indicating that the rect is rotated 90 degrees clockwise.
+const FLAG_ROTATED: u32 = 1;
+
+fn read_u16(data: &[u8], offset: usize) -> u16
+{
+	data[offset] as u16 | (data[offset + 1] as u16) << 8
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32
+{
+	read_u16(data, offset) as u32 | (read_u16(data, offset + 2) as u32) << 16
+}
+
+fn write_u16(buffer: &mut Vec<u8>, value: u16)
+{
+	buffer.extend_from_slice(&[value as u8, (value >> 8) as u8]);
+}
+
+fn write_u32(buffer: &mut Vec<u8>, value: u32)
+{
+	write_u16(buffer, value as u16);
+	write_u16(buffer, (value >> 16) as u16);
+}
+
+/// An error indicating that bytes are not a valid binary layout.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BinaryError
+{
+	/// The bytes do not start with `MAGIC`.
+	Magic,
+
+	/// The layout has the given version, which is newer than `VERSION`.
+	Version(u16),
+
+	/// The bytes end before the tables described by the header.
+	Truncated,
+
+	/// The name of the region with the given index is out of bounds or not UTF-8.
+	Name(usize),
+}
+
+impl fmt::Display for BinaryError
+{
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+	{
+		match *self
+		{
+			BinaryError::Magic => write!(f, "not a binary layout"),
+			BinaryError::Version(version) => write!(f, "unsupported binary layout version {}", version),
+			BinaryError::Truncated => write!(f, "binary layout is truncated"),
+			BinaryError::Name(region_index) => write!(f, "region {} has an invalid name", region_index),
+		}
+	}
+}
+
+impl Error for BinaryError {}
+
+/// A rect placed in a bin, as stored in a binary layout.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct RegionRecord<'a>
+{
+	/// The name of the rect, which is empty if the rect was not named.
+	pub name: &'a str,
+
+	/// The index of the rect in the rect list of the atlas.
+	pub rect_index: usize,
+
+	/// The index of the bin that the rect is in.
+	pub bin: usize,
+
+	/// The x-position of the rect in the bin.
+	pub x: u32,
+
+	/// The y-position of the rect in the bin.
+	pub y: u32,
+
+	/// The width of the rect before rotation.
+	pub width: u32,
+
+	/// The height of the rect before rotation.
+	pub height: u32,
+
+	/// Whether the rect is rotated 90 degrees clockwise, taking up its height in width in the bin.
+	pub rotated: bool,
+
+	/// How the rect was cropped out of its source image.
+	pub trim: Trim,
+}
+
+/// Reads a binary layout without copying it.
+///
+/// A binary layout is little-endian and starts with a header, followed by a table of bin sizes, a
+/// table of fixed size region records and a table of UTF-8 names referenced by the records. The
+/// header and the names are checked once when the reader is created, after which bins and regions
+/// are decoded directly from the bytes whenever they are accessed.
+///
+/// Reading a layout does not need the `image` feature, so that a runtime can load the layout of an
+/// atlas whose images were composed by a build script.
+///
+#[derive(Copy, Clone, Debug)]
+pub struct LayoutReader<'a>
+{
+	data: &'a [u8],
+	version: u16,
+	bin_count: usize,
+	region_count: usize,
+	string_offset: usize,
+	string_size: usize,
+}
+
+impl<'a> LayoutReader<'a>
+{
+	/// Checks the given bytes and creates a reader over them.
+	pub fn new(data: &'a [u8]) -> Result<Self, BinaryError>
+	{
+		if data.len() < HEADER_SIZE
+		{
+			return Err(if data.len() >= 4 && data[..4] != MAGIC { BinaryError::Magic } else { BinaryError::Truncated });
+		}
+		if data[..4] != MAGIC
+		{
+			return Err(BinaryError::Magic);
+		}
+		let version = read_u16(data, 4);
+		if version > VERSION
+		{
+			return Err(BinaryError::Version(version));
+		}
+
+		let bin_count = read_u32(data, 8) as usize;
+		let region_count = read_u32(data, 12) as usize;
+		let string_size = read_u32(data, 16) as usize;
+		let string_offset = bin_count.checked_mul(BIN_SIZE)
+			.and_then(|bin_size| region_count.checked_mul(REGION_SIZE).and_then(|region_size| bin_size.checked_add(region_size)))
+			.and_then(|table_size| table_size.checked_add(HEADER_SIZE))
+			.ok_or(BinaryError::Truncated)?;
+		if string_offset.checked_add(string_size).is_none_or(|size| size > data.len())
+		{
+			return Err(BinaryError::Truncated);
+		}
+
+		let reader = LayoutReader
+		{
+			data,
+			version,
+			bin_count,
+			region_count,
+			string_offset,
+			string_size,
+		};
+		for region_index in 0..region_count
+		{
+			reader.name(region_index).ok_or(BinaryError::Name(region_index))?;
+		}
+		Ok(reader)
+	}
+
+	/// Returns the version of the layout.
+	pub fn version(&self) -> u16
+	{
+		self.version
+	}
+
+	/// Returns the number of bins.
+	pub fn bin_count(&self) -> usize
+	{
+		self.bin_count
+	}
+
+	/// Returns the dimensions of the indicated bin.
+	pub fn bin(&self, bin_index: usize) -> Rect
+	{
+		assert!(bin_index < self.bin_count);
+		let offset = HEADER_SIZE + bin_index * BIN_SIZE;
+		Rect::new(read_u32(self.data, offset), read_u32(self.data, offset + 4))
+	}
+
+	/// Returns the number of regions.
+	pub fn region_count(&self) -> usize
+	{
+		self.region_count
+	}
+
+	/// Returns the indicated region.
+	pub fn region(&self, region_index: usize) -> RegionRecord<'a>
+	{
+		assert!(region_index < self.region_count);
+		let offset = HEADER_SIZE + self.bin_count * BIN_SIZE + region_index * REGION_SIZE;
+		let field = |index: usize| read_u32(self.data, offset + index * 4);
+		RegionRecord
+		{
+			name: self.name(region_index).unwrap(),
+			rect_index: field(2) as usize,
+			bin: field(3) as usize,
+			x: field(4),
+			y: field(5),
+			width: field(6),
+			height: field(7),
+			rotated: field(8) & FLAG_ROTATED != 0,
+			trim: Trim::new(field(9), field(10), field(11), field(12)),
+		}
+	}
+
+	/// Returns the first region with the given name.
+	pub fn region_find(&self, name: &str) -> Option<RegionRecord<'a>>
+	{
+		(0..self.region_count).map(|region_index| self.region(region_index)).find(|region| region.name == name)
+	}
+
+	/// Returns the name of the indicated region, if it is within the string table and UTF-8.
+	fn name(&self, region_index: usize) -> Option<&'a str>
+	{
+		let offset = HEADER_SIZE + self.bin_count * BIN_SIZE + region_index * REGION_SIZE;
+		let name_offset = read_u32(self.data, offset) as usize;
+		let name_length = read_u32(self.data, offset + 4) as usize;
+		if name_offset.checked_add(name_length).is_none_or(|name_end| name_end > self.string_size)
+		{
+			return None;
+		}
+		let start = self.string_offset + name_offset;
+		str::from_utf8(&self.data[start..start + name_length]).ok()
+	}
+}
+
+/// Writes the layout of an atlas in the format read by `LayoutReader`, or as the metadata of other
+/// tools with `write_texture_packer` and `write_libgdx`.
+///
+/// Every placed rect is written as a region, in order of bins and then parts. Rects can be given
+/// names and trims. Rects without a name are written with an empty name, and rects without a trim
+/// are written as if they were not cropped.
+///
+pub struct LayoutWriter<'a>
+{
+	name_list: &'a [&'a str],
+	trim_list: &'a [Trim],
+}
+
+impl<'a> LayoutWriter<'a>
+{
+	/// Creates a writer without any names or trims.
+	pub fn new() -> Self
+	{
+		LayoutWriter
+		{
+			name_list: &[],
+			trim_list: &[],
+		}
+	}
+
+	/// Sets the names of the rects, by rect index.
+	pub fn name_list(mut self, name_list: &'a [&'a str]) -> Self
+	{
+		self.name_list = name_list;
+		self
+	}
+
+	/// Sets the trims of the rects, by rect index.
+	pub fn trim_list(mut self, trim_list: &'a [Trim]) -> Self
+	{
+		self.trim_list = trim_list;
+		self
+	}
+
+	/// Returns the binary layout of the given atlas.
+	pub fn write<T: AtlasRect>(&self, atlas: &Atlas<T>) -> Vec<u8>
+	{
+		let region_count: usize = atlas.bin_list().iter().map(|bin| bin.part_list().len()).sum();
+		let string_size: usize = atlas.bin_list().iter()
+			.flat_map(|bin| bin.part_list())
+			.map(|part| self.name_list.get(part.rect_index).map_or(0, |name| name.len()))
+			.sum();
+
+		let mut buffer = Vec::with_capacity(HEADER_SIZE + atlas.bin_list().len() * BIN_SIZE + region_count * REGION_SIZE + string_size);
+		buffer.extend_from_slice(&MAGIC);
+		write_u16(&mut buffer, VERSION);
+		write_u16(&mut buffer, 0);
+		write_u32(&mut buffer, atlas.bin_list().len() as u32);
+		write_u32(&mut buffer, region_count as u32);
+		write_u32(&mut buffer, string_size as u32);
+
+		for bin in atlas.bin_list()
+		{
+			write_u32(&mut buffer, bin.dimensions().width);
+			write_u32(&mut buffer, bin.dimensions().height);
+		}
+
+		let mut string_list: Vec<u8> = Vec::with_capacity(string_size);
+		for (bin_index, bin) in atlas.bin_list().iter().enumerate()
+		{
+			for part in bin.part_list()
+			{
+				let name = self.rect_name(part.rect_index).unwrap_or("");
+				let rect = &atlas.rect_list()[part.rect_index];
+				let (width, height) = (rect.width(), rect.height());
+				let trim = self.rect_trim(part.rect_index, width, height);
+				let field_list =
+				[
+					string_list.len() as u32,
+					name.len() as u32,
+					part.rect_index as u32,
+					bin_index as u32,
+					part.x,
+					part.y,
+					width,
+					height,
+					if part.rotate { FLAG_ROTATED } else { 0 },
+					trim.x,
+					trim.y,
+					trim.source_width,
+					trim.source_height,
+				];
+				for &field in field_list.iter()
+				{
+					write_u32(&mut buffer, field);
+				}
+				string_list.extend_from_slice(name.as_bytes());
+			}
+		}
+		buffer.extend_from_slice(&string_list);
+		buffer
+	}
+
+	/// Returns the name of the indicated rect, if it was given one.
+	pub(crate) fn rect_name(&self, rect_index: usize) -> Option<&'a str>
+	{
+		self.name_list.get(rect_index).cloned()
+	}
+
+	/// Returns the trim of the indicated rect, which has the given dimensions.
+	pub(crate) fn rect_trim(&self, rect_index: usize, width: u32, height: u32) -> Trim
+	{
+		self.trim_list.get(rect_index).cloned().unwrap_or_else(|| Trim::none(width, height))
+	}
+}
+
+impl<'a> Default for LayoutWriter<'a>
+{
+	fn default() -> Self
+	{
+		LayoutWriter::new()
+	}
+}
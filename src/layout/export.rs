@@ -0,0 +1,86 @@
+use std::fmt::Write;
+
+use {Atlas, AtlasRect};
+use layout::LayoutWriter;
+use layout::json::json_string;
+
+impl<'a> LayoutWriter<'a>
+{
+	/// Returns the name of the indicated rect, or its index if it was not given one.
+	fn export_name(&self, rect_index: usize) -> String
+	{
+		self.rect_name(rect_index).map_or_else(|| rect_index.to_string(), str::to_string)
+	}
+
+	/// Returns the indicated bin as the JSON hash metadata of TexturePacker, for the image with the
+	/// given file name.
+	///
+	/// TexturePacker writes one file per bin, so each bin is written separately. Rotated rects are
+	/// written with `"rotated": true`, which TexturePacker also uses for rects that are rotated 90
+	/// degrees clockwise.
+	///
+	pub fn write_texture_packer<T: AtlasRect>(&self, atlas: &Atlas<T>, bin_index: usize, image_name: &str) -> String
+	{
+		let bin = &atlas.bin_list()[bin_index];
+		let mut text = String::from("{\"frames\": {");
+		for (part_index, part) in bin.part_list().iter().enumerate()
+		{
+			let rect = &atlas.rect_list()[part.rect_index];
+			let (width, height) = (rect.width(), rect.height());
+			let trim = self.rect_trim(part.rect_index, width, height);
+			write!(text, "{}\n\t{}: {{\"frame\": {{\"x\": {}, \"y\": {}, \"w\": {}, \"h\": {}}}, \"rotated\": {}, \"trimmed\": {}, \
+				\"spriteSourceSize\": {{\"x\": {}, \"y\": {}, \"w\": {}, \"h\": {}}}, \"sourceSize\": {{\"w\": {}, \"h\": {}}}}}",
+				if part_index == 0 { "" } else { "," },
+				json_string(&self.export_name(part.rect_index)),
+				part.x, part.y, width, height,
+				part.rotate,
+				trim.is_trimmed(width, height),
+				trim.x, trim.y, width, height,
+				trim.source_width, trim.source_height).unwrap();
+		}
+		write!(text, "\n}},\n\"meta\": {{\"app\": \"texture_atlas\", \"image\": {}, \"format\": \"RGBA8888\", \
+			\"size\": {{\"w\": {}, \"h\": {}}}, \"scale\": \"1\"}}\n}}\n",
+			json_string(image_name),
+			bin.dimensions().width, bin.dimensions().height).unwrap();
+		text
+	}
+
+	/// Returns the atlas as the `.atlas` metadata of libGDX, with one page per bin using the image
+	/// with the given file name. Nothing is returned if there is not one image name per bin.
+	///
+	/// Rotated rects are written with `rotate: true`, which libGDX reads as a rotation of 90 degrees
+	/// counter-clockwise, the opposite of the rotation of this crate. The region of every rect is
+	/// kept, but rotated rects of images composed by this crate appear upside down in libGDX, so
+	/// atlases meant for libGDX are best generated without rotation.
+	///
+	pub fn write_libgdx<T: AtlasRect>(&self, atlas: &Atlas<T>, image_name_list: &[&str]) -> Option<String>
+	{
+		if image_name_list.len() != atlas.bin_list().len()
+		{
+			return None;
+		}
+
+		let mut text = String::new();
+		for (bin, image_name) in atlas.bin_list().iter().zip(image_name_list)
+		{
+			write!(text, "\n{}\nsize: {}, {}\nformat: RGBA8888\nfilter: Nearest, Nearest\nrepeat: none\n",
+				image_name, bin.dimensions().width, bin.dimensions().height).unwrap();
+			for part in bin.part_list()
+			{
+				let rect = &atlas.rect_list()[part.rect_index];
+				let (width, height) = (rect.width(), rect.height());
+				let trim = self.rect_trim(part.rect_index, width, height);
+
+				// The offset of libGDX is measured from the bottom of the source image.
+				write!(text, "{}\n  rotate: {}\n  xy: {}, {}\n  size: {}, {}\n  orig: {}, {}\n  offset: {}, {}\n  index: -1\n",
+					self.export_name(part.rect_index),
+					part.rotate,
+					part.x, part.y,
+					width, height,
+					trim.source_width, trim.source_height,
+					trim.x, trim.source_height.saturating_sub(trim.y + height)).unwrap();
+			}
+		}
+		Some(text)
+	}
+}
@@ -0,0 +1,513 @@
+use std::error::Error;
+use std::fmt;
+
+#[cfg(feature = "image")]
+use image::{DynamicImage, GenericImage, RgbaImage};
+
+use AtlasBin;
+use layout::{AtlasLayout, LayoutReader, Trim};
+use layout::json::{json_parse, Json};
+use util::{Rect, Region};
+
+/// How an imported sprite is rotated in its page.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Rotation
+{
+	/// The sprite is not rotated.
+	None,
+
+	/// The sprite is rotated 90 degrees clockwise, like rotated rects of this crate and of
+	/// TexturePacker.
+	Clockwise,
+
+	/// The sprite is rotated 90 degrees counter-clockwise, like rotated regions of libGDX.
+	CounterClockwise,
+}
+
+/// An error indicating that the metadata of an atlas could not be imported.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ImportError
+{
+	/// The metadata is malformed at the given byte offset of JSON or line of an `.atlas` file.
+	Syntax(usize),
+
+	/// The field with the given name is missing or has an invalid value.
+	Field(String),
+
+	/// The sprite with the given name does not fit inside of its page or source image.
+	Bounds(String),
+
+	/// The atlas has the first number of pages, but the second number of page images was given.
+	PageCount(usize, usize),
+
+	/// The image of the page with the given index has different dimensions than in the metadata.
+	PageSize(usize),
+}
+
+impl fmt::Display for ImportError
+{
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+	{
+		match *self
+		{
+			ImportError::Syntax(position) => write!(f, "malformed atlas metadata at {}", position),
+			ImportError::Field(ref name) => write!(f, "missing or invalid field `{}`", name),
+			ImportError::Bounds(ref name) => write!(f, "sprite `{}` is out of bounds", name),
+			ImportError::PageCount(expected, found) => write!(f, "atlas has {} pages but {} images were given", expected, found),
+			ImportError::PageSize(page_index) => write!(f, "image of page {} does not match the size in the metadata", page_index),
+		}
+	}
+}
+
+impl Error for ImportError {}
+
+/// An image of an imported atlas.
+#[derive(Clone, Debug)]
+pub struct ImportedPage
+{
+	/// The file name of the image, relative to the metadata.
+	pub image_name: String,
+
+	/// The dimensions of the image.
+	pub dimensions: Rect,
+}
+
+/// A sprite placed in a page of an imported atlas.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ImportedSprite
+{
+	/// The name of the sprite.
+	pub name: String,
+
+	/// The index of the page that the sprite is in.
+	pub page: usize,
+
+	/// The x-position of the sprite in the page.
+	pub x: u32,
+
+	/// The y-position of the sprite in the page.
+	pub y: u32,
+
+	/// The width of the sprite before rotation.
+	pub width: u32,
+
+	/// The height of the sprite before rotation.
+	pub height: u32,
+
+	/// How the sprite is rotated in the page.
+	pub rotation: Rotation,
+
+	/// How the sprite was cropped out of its source image.
+	pub trim: Trim,
+}
+
+impl ImportedSprite
+{
+	/// Returns the region that the sprite takes up in its page.
+	pub fn region(&self) -> Region
+	{
+		match self.rotation
+		{
+			Rotation::None => Region::new(self.x, self.y, self.width, self.height),
+			_ => Region::new(self.x, self.y, self.height, self.width),
+		}
+	}
+}
+
+/// The layout of an atlas read from the metadata of another tool.
+///
+/// TexturePacker JSON files in both the hash and the array format are read with
+/// `from_texture_packer`, and libGDX `.atlas` files in both the legacy and the current format are
+/// read with `from_libgdx`. Both formats are also written by `LayoutWriter`, as are binary layouts,
+/// which are read back with `from_binary`.
+///
+/// With the `image` feature, `unpack` cuts the images of the pages back into one image per sprite,
+/// so that the sprites of an existing atlas can be packed again. `layout` converts the placements
+/// of the sprites into an `AtlasLayout`, for instance to keep them with
+/// `AtlasBuilder::incremental_layout`.
+///
+#[derive(Clone, Debug, Default)]
+pub struct ImportedAtlas
+{
+	page_list: Vec<ImportedPage>,
+	sprite_list: Vec<ImportedSprite>,
+}
+
+fn field(name: &str) -> ImportError
+{
+	ImportError::Field(name.to_string())
+}
+
+/// Returns the indicated member of a JSON object as a dimension or position.
+fn json_u32(value: &Json, key: &str) -> Result<u32, ImportError>
+{
+	value.get(key).and_then(Json::as_u32).ok_or_else(|| field(key))
+}
+
+/// Parses the comma separated values of a field of an `.atlas` file.
+fn libgdx_value_list(value: &str, count: usize) -> Option<Vec<u32>>
+{
+	let value_list: Vec<u32> = value.split(',').map(|value| value.trim().parse()).collect::<Result<_, _>>().ok()?;
+	if value_list.len() == count { Some(value_list) } else { None }
+}
+
+/// The fields of a region of an `.atlas` file, as they are read.
+struct LibgdxRegion
+{
+	name: String,
+	page: usize,
+	rotation: Rotation,
+	position: Option<(u32, u32)>,
+	size: Option<(u32, u32)>,
+	orig: Option<(u32, u32)>,
+	offset: (u32, u32),
+}
+
+impl LibgdxRegion
+{
+	/// Reads the given field, returning `None` if the value is invalid.
+	fn field(&mut self, key: &str, value: &str) -> Option<()>
+	{
+		match key
+		{
+			"rotate" =>
+			{
+				self.rotation = match value
+				{
+					"false" | "0" => Rotation::None,
+					"true" | "90" => Rotation::CounterClockwise,
+					_ => return None,
+				};
+			}
+			"xy" => self.position = Some(libgdx_value_list(value, 2).map(|list| (list[0], list[1]))?),
+			"size" => self.size = Some(libgdx_value_list(value, 2).map(|list| (list[0], list[1]))?),
+			"bounds" =>
+			{
+				let list = libgdx_value_list(value, 4)?;
+				self.position = Some((list[0], list[1]));
+				self.size = Some((list[2], list[3]));
+			}
+			"orig" => self.orig = Some(libgdx_value_list(value, 2).map(|list| (list[0], list[1]))?),
+			"offset" => self.offset = libgdx_value_list(value, 2).map(|list| (list[0], list[1]))?,
+			"offsets" =>
+			{
+				let list = libgdx_value_list(value, 4)?;
+				self.offset = (list[0], list[1]);
+				self.orig = Some((list[2], list[3]));
+			}
+			_ => {}
+		}
+		Some(())
+	}
+
+	fn sprite(self) -> Result<ImportedSprite, ImportError>
+	{
+		let (x, y) = self.position.ok_or_else(|| field("xy"))?;
+		let (width, height) = self.size.ok_or_else(|| field("size"))?;
+		let (source_width, source_height) = self.orig.unwrap_or((width, height));
+
+		// The offset of libGDX is measured from the bottom of the source image.
+		let trim_y = source_height.checked_sub(self.offset.1)
+			.and_then(|bottom| bottom.checked_sub(height))
+			.ok_or_else(|| ImportError::Bounds(self.name.clone()))?;
+		Ok(ImportedSprite
+		{
+			name: self.name,
+			page: self.page,
+			x,
+			y,
+			width,
+			height,
+			rotation: self.rotation,
+			trim: Trim::new(self.offset.0, trim_y, source_width, source_height),
+		})
+	}
+}
+
+impl ImportedAtlas
+{
+	/// Returns the pages of the atlas.
+	pub fn page_list(&self) -> &[ImportedPage]
+	{
+		&self.page_list
+	}
+
+	/// Returns the sprites of the atlas, in the order that they were read.
+	pub fn sprite_list(&self) -> &[ImportedSprite]
+	{
+		&self.sprite_list
+	}
+
+	/// Returns the first sprite with the given name.
+	pub fn sprite_find(&self, name: &str) -> Option<&ImportedSprite>
+	{
+		self.sprite_list.iter().find(|sprite| sprite.name == name)
+	}
+
+	/// Appends the pages and sprites of the given atlas, for instance to combine the files that
+	/// TexturePacker writes for each page.
+	pub fn merge(mut self, other: ImportedAtlas) -> Self
+	{
+		let page_offset = self.page_list.len();
+		self.page_list.extend(other.page_list);
+		self.sprite_list.extend(other.sprite_list.into_iter().map(|mut sprite|
+		{
+			sprite.page += page_offset;
+			sprite
+		}));
+		self
+	}
+
+	/// Reads the JSON metadata written by TexturePacker for a single page.
+	pub fn from_texture_packer(text: &str) -> Result<Self, ImportError>
+	{
+		let root = json_parse(text).map_err(ImportError::Syntax)?;
+		let meta = root.get("meta").ok_or_else(|| field("meta"))?;
+		let image_name = meta.get("image").and_then(Json::as_str).ok_or_else(|| field("image"))?;
+		let size = meta.get("size").ok_or_else(|| field("size"))?;
+		let page = ImportedPage
+		{
+			image_name: image_name.to_string(),
+			dimensions: Rect::new(json_u32(size, "w")?, json_u32(size, "h")?),
+		};
+
+		let frame_list: Vec<(&str, &Json)> = match root.get("frames")
+		{
+			Some(Json::Object(member_list)) => member_list.iter().map(|member| (member.0.as_str(), &member.1)).collect(),
+			Some(Json::Array(value_list)) => value_list.iter()
+				.map(|value| value.get("filename").and_then(Json::as_str).map(|name| (name, value)).ok_or_else(|| field("filename")))
+				.collect::<Result<_, _>>()?,
+			_ => return Err(field("frames")),
+		};
+
+		let mut sprite_list = Vec::with_capacity(frame_list.len());
+		for (name, value) in frame_list
+		{
+			let frame = value.get("frame").ok_or_else(|| field("frame"))?;
+			let (width, height) = (json_u32(frame, "w")?, json_u32(frame, "h")?);
+			let rotated = match value.get("rotated")
+			{
+				Some(rotated) => rotated.as_bool().ok_or_else(|| field("rotated"))?,
+				None => false,
+			};
+			let (trim_x, trim_y) = match value.get("spriteSourceSize")
+			{
+				Some(source) => (json_u32(source, "x")?, json_u32(source, "y")?),
+				None => (0, 0),
+			};
+			let (source_width, source_height) = match value.get("sourceSize")
+			{
+				Some(source) => (json_u32(source, "w")?, json_u32(source, "h")?),
+				None => (width, height),
+			};
+			sprite_list.push(ImportedSprite
+			{
+				name: name.to_string(),
+				page: 0,
+				x: json_u32(frame, "x")?,
+				y: json_u32(frame, "y")?,
+				width,
+				height,
+				rotation: if rotated { Rotation::Clockwise } else { Rotation::None },
+				trim: Trim::new(trim_x, trim_y, source_width, source_height),
+			});
+		}
+
+		let atlas = ImportedAtlas
+		{
+			page_list: vec![page],
+			sprite_list,
+		};
+		atlas.check()?;
+		Ok(atlas)
+	}
+
+	/// Reads an `.atlas` file written by libGDX, which may have many pages.
+	pub fn from_libgdx(text: &str) -> Result<Self, ImportError>
+	{
+		let mut atlas = ImportedAtlas::default();
+		let mut in_page = false;
+		let mut region: Option<LibgdxRegion> = None;
+		for (line_index, line) in text.lines().enumerate()
+		{
+			let line = line.trim();
+			if line.is_empty()
+			{
+				in_page = false;
+				continue;
+			}
+			if !in_page
+			{
+				if let Some(region) = region.take()
+				{
+					atlas.sprite_list.push(region.sprite()?);
+				}
+				atlas.page_list.push(ImportedPage
+				{
+					image_name: line.to_string(),
+					dimensions: Rect::new(0, 0),
+				});
+				in_page = true;
+				continue;
+			}
+
+			let page = atlas.page_list.len() - 1;
+			match line.find(':')
+			{
+				Some(colon) =>
+				{
+					let (key, value) = (line[..colon].trim(), line[colon + 1..].trim());
+					match region
+					{
+						Some(ref mut region) => region.field(key, value).ok_or(ImportError::Syntax(line_index + 1))?,
+						None if key == "size" =>
+						{
+							let list = libgdx_value_list(value, 2).ok_or(ImportError::Syntax(line_index + 1))?;
+							atlas.page_list[page].dimensions = Rect::new(list[0], list[1]);
+						}
+						None => {}
+					}
+				}
+				None =>
+				{
+					if let Some(region) = region.take()
+					{
+						atlas.sprite_list.push(region.sprite()?);
+					}
+					region = Some(LibgdxRegion
+					{
+						name: line.to_string(),
+						page,
+						rotation: Rotation::None,
+						position: None,
+						size: None,
+						orig: None,
+						offset: (0, 0),
+					});
+				}
+			}
+		}
+		if let Some(region) = region
+		{
+			atlas.sprite_list.push(region.sprite()?);
+		}
+
+		atlas.check()?;
+		Ok(atlas)
+	}
+
+	/// Reads a binary layout written by `LayoutWriter`, with one page per bin.
+	///
+	/// Binary layouts do not name the images of their bins, so the image names of the pages are
+	/// empty. Sprites are in the order of the rects of the atlas, and unnamed rects are named by
+	/// their index like in the exported formats.
+	///
+	pub fn from_binary(reader: &LayoutReader) -> Result<Self, ImportError>
+	{
+		let page_list = (0..reader.bin_count()).map(|bin_index| ImportedPage
+		{
+			image_name: String::new(),
+			dimensions: reader.bin(bin_index),
+		}).collect();
+
+		let mut record_list: Vec<_> = (0..reader.region_count()).map(|region_index| reader.region(region_index)).collect();
+		record_list.sort_by_key(|record| record.rect_index);
+		let sprite_list = record_list.into_iter().map(|record| ImportedSprite
+		{
+			name: if record.name.is_empty() { record.rect_index.to_string() } else { record.name.to_string() },
+			page: record.bin,
+			x: record.x,
+			y: record.y,
+			width: record.width,
+			height: record.height,
+			rotation: if record.rotated { Rotation::Clockwise } else { Rotation::None },
+			trim: record.trim,
+		}).collect();
+
+		let atlas = ImportedAtlas
+		{
+			page_list,
+			sprite_list,
+		};
+		atlas.check()?;
+		Ok(atlas)
+	}
+
+	/// Returns a layout with one bin per page, where the rects are the sprites in order.
+	///
+	/// The rects have the trimmed sizes of the sprites rather than the sizes of their source images.
+	/// Sprites rotated counter-clockwise keep their region, but are rotated clockwise like every
+	/// rotated rect of this crate when the layout is applied.
+	///
+	pub fn layout(&self) -> AtlasLayout
+	{
+		let rect_list: Vec<Rect> = self.sprite_list.iter().map(|sprite| Rect::new(sprite.width, sprite.height)).collect();
+		let mut bin_list: Vec<AtlasBin> = self.page_list.iter().map(|page| AtlasBin::new(page.dimensions, Vec::new())).collect();
+		for (rect_index, sprite) in self.sprite_list.iter().enumerate()
+		{
+			let region = sprite.region();
+			bin_list[sprite.page].part_add(rect_index, region.x, region.y, region.dimensions(), sprite.rotation != Rotation::None);
+		}
+		AtlasLayout::from_bins(rect_list, bin_list)
+	}
+
+	/// Returns an error if a sprite does not fit inside of its page or source image.
+	fn check(&self) -> Result<(), ImportError>
+	{
+		for sprite in &self.sprite_list
+		{
+			let page = self.page_list.get(sprite.page).ok_or_else(|| ImportError::Bounds(sprite.name.clone()))?;
+			let region = sprite.region();
+			let trim = &sprite.trim;
+			let fits = |position: u32, length: u32, limit: u32| position as u64 + length as u64 <= limit as u64;
+			if !fits(region.x, region.width, page.dimensions.width) || !fits(region.y, region.height, page.dimensions.height)
+				|| !fits(trim.x, sprite.width, trim.source_width) || !fits(trim.y, sprite.height, trim.source_height)
+			{
+				return Err(ImportError::Bounds(sprite.name.clone()));
+			}
+		}
+		Ok(())
+	}
+
+	/// Cuts the given images of the pages into one image per sprite, in the order of the sprites.
+	///
+	/// Rotated sprites are rotated back, and trimmed sprites are placed inside of a transparent
+	/// image of the size of their source image. Returns an error if the images do not match the
+	/// pages of the metadata.
+	///
+	#[cfg(feature = "image")]
+	pub fn unpack(&self, page_image_list: &[DynamicImage]) -> Result<Vec<DynamicImage>, ImportError>
+	{
+		if page_image_list.len() != self.page_list.len()
+		{
+			return Err(ImportError::PageCount(self.page_list.len(), page_image_list.len()));
+		}
+		for (page_index, (image, page)) in page_image_list.iter().zip(&self.page_list).enumerate()
+		{
+			if image.width() != page.dimensions.width || image.height() != page.dimensions.height
+			{
+				return Err(ImportError::PageSize(page_index));
+			}
+		}
+		let page_list: Vec<RgbaImage> = page_image_list.iter().map(DynamicImage::to_rgba).collect();
+		Ok(self.sprite_list.iter().map(|sprite|
+		{
+			let page = &page_list[sprite.page];
+			let mut image = RgbaImage::new(sprite.trim.source_width, sprite.trim.source_height);
+			for y in 0..sprite.height
+			{
+				for x in 0..sprite.width
+				{
+					let (page_x, page_y) = match sprite.rotation
+					{
+						Rotation::None => (sprite.x + x, sprite.y + y),
+						Rotation::Clockwise => (sprite.x + sprite.height - 1 - y, sprite.y + x),
+						Rotation::CounterClockwise => (sprite.x + y, sprite.y + sprite.width - 1 - x),
+					};
+					image.put_pixel(sprite.trim.x + x, sprite.trim.y + y, *page.get_pixel(page_x, page_y));
+				}
+			}
+			DynamicImage::ImageRgba8(image)
+		}).collect())
+	}
+}
@@ -0,0 +1,354 @@
+use std::char;
+use std::fmt::Write;
+
+/// A JSON value, with the members of objects kept in order.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Json
+{
+	Null,
+	Bool(bool),
+	Number(f64),
+	String(String),
+	Array(Vec<Json>),
+	Object(Vec<(String, Json)>),
+}
+
+impl Json
+{
+	/// Returns the first member of an object with the given key.
+	pub(crate) fn get(&self, key: &str) -> Option<&Json>
+	{
+		match *self
+		{
+			Json::Object(ref member_list) => member_list.iter().find(|member| member.0 == key).map(|member| &member.1),
+			_ => None,
+		}
+	}
+
+	pub(crate) fn as_bool(&self) -> Option<bool>
+	{
+		match *self
+		{
+			Json::Bool(value) => Some(value),
+			_ => None,
+		}
+	}
+
+	/// Returns the value of a number that is a non-negative integer fitting in 32 bits.
+	pub(crate) fn as_u32(&self) -> Option<u32>
+	{
+		match *self
+		{
+			Json::Number(value) if value >= 0.0 && value <= u32::MAX as f64 && value.fract() == 0.0 => Some(value as u32),
+			_ => None,
+		}
+	}
+
+	pub(crate) fn as_str(&self) -> Option<&str>
+	{
+		match *self
+		{
+			Json::String(ref value) => Some(value),
+			_ => None,
+		}
+	}
+}
+
+/// The deepest nesting of arrays and objects that is parsed before giving up.
+const DEPTH_LIMIT: usize = 128;
+
+/// Parses a JSON document, returning the byte offset of the first error on failure.
+pub(crate) fn json_parse(text: &str) -> Result<Json, usize>
+{
+	let mut parser = Parser
+	{
+		data: text.as_bytes(),
+		offset: 0,
+		depth: 0,
+	};
+	let value = parser.value()?;
+	parser.space();
+	if parser.offset != parser.data.len()
+	{
+		return Err(parser.offset);
+	}
+	Ok(value)
+}
+
+/// Returns the given text as a quoted JSON string.
+pub(crate) fn json_string(text: &str) -> String
+{
+	let mut quoted = String::with_capacity(text.len() + 2);
+	quoted.push('"');
+	for character in text.chars()
+	{
+		match character
+		{
+			'"' => quoted.push_str("\\\""),
+			'\\' => quoted.push_str("\\\\"),
+			'\n' => quoted.push_str("\\n"),
+			'\r' => quoted.push_str("\\r"),
+			'\t' => quoted.push_str("\\t"),
+			character if (character as u32) < 0x20 =>
+			{
+				write!(quoted, "\\u{:04x}", character as u32).unwrap();
+			}
+			character => quoted.push(character),
+		}
+	}
+	quoted.push('"');
+	quoted
+}
+
+struct Parser<'a>
+{
+	data: &'a [u8],
+	offset: usize,
+	depth: usize,
+}
+
+impl<'a> Parser<'a>
+{
+	fn space(&mut self)
+	{
+		while self.offset < self.data.len() && (self.data[self.offset] as char).is_ascii_whitespace()
+		{
+			self.offset += 1;
+		}
+	}
+
+	fn peek(&mut self) -> Option<u8>
+	{
+		self.space();
+		self.data.get(self.offset).cloned()
+	}
+
+	fn expect(&mut self, byte: u8) -> Result<(), usize>
+	{
+		if self.peek() != Some(byte)
+		{
+			return Err(self.offset);
+		}
+		self.offset += 1;
+		Ok(())
+	}
+
+	fn keyword(&mut self, keyword: &str, value: Json) -> Result<Json, usize>
+	{
+		if !self.data[self.offset..].starts_with(keyword.as_bytes())
+		{
+			return Err(self.offset);
+		}
+		self.offset += keyword.len();
+		Ok(value)
+	}
+
+	fn value(&mut self) -> Result<Json, usize>
+	{
+		match self.peek()
+		{
+			Some(b'{') | Some(b'[') if self.depth == DEPTH_LIMIT => Err(self.offset),
+			Some(b'{') =>
+			{
+				self.depth += 1;
+				let value = self.object();
+				self.depth -= 1;
+				value
+			}
+			Some(b'[') =>
+			{
+				self.depth += 1;
+				let value = self.array();
+				self.depth -= 1;
+				value
+			}
+			Some(b'"') => self.string().map(Json::String),
+			Some(b't') => self.keyword("true", Json::Bool(true)),
+			Some(b'f') => self.keyword("false", Json::Bool(false)),
+			Some(b'n') => self.keyword("null", Json::Null),
+			Some(b'-') | Some(b'0'..=b'9') => self.number(),
+			_ => Err(self.offset),
+		}
+	}
+
+	fn object(&mut self) -> Result<Json, usize>
+	{
+		self.expect(b'{')?;
+		let mut member_list = Vec::new();
+		if self.peek() == Some(b'}')
+		{
+			self.offset += 1;
+			return Ok(Json::Object(member_list));
+		}
+		loop
+		{
+			if self.peek() != Some(b'"')
+			{
+				return Err(self.offset);
+			}
+			let key = self.string()?;
+			self.expect(b':')?;
+			member_list.push((key, self.value()?));
+			match self.peek()
+			{
+				Some(b',') => self.offset += 1,
+				Some(b'}') =>
+				{
+					self.offset += 1;
+					return Ok(Json::Object(member_list));
+				}
+				_ => return Err(self.offset),
+			}
+		}
+	}
+
+	fn array(&mut self) -> Result<Json, usize>
+	{
+		self.expect(b'[')?;
+		let mut value_list = Vec::new();
+		if self.peek() == Some(b']')
+		{
+			self.offset += 1;
+			return Ok(Json::Array(value_list));
+		}
+		loop
+		{
+			value_list.push(self.value()?);
+			match self.peek()
+			{
+				Some(b',') => self.offset += 1,
+				Some(b']') =>
+				{
+					self.offset += 1;
+					return Ok(Json::Array(value_list));
+				}
+				_ => return Err(self.offset),
+			}
+		}
+	}
+
+	/// Skips decimal digits, returning how many there were.
+	fn digits(&mut self) -> usize
+	{
+		let start = self.offset;
+		while self.data.get(self.offset).is_some_and(u8::is_ascii_digit)
+		{
+			self.offset += 1;
+		}
+		self.offset - start
+	}
+
+	/// Skips the given byte, returning whether it was there.
+	fn skip(&mut self, byte: u8) -> bool
+	{
+		let found = self.data.get(self.offset) == Some(&byte);
+		if found
+		{
+			self.offset += 1;
+		}
+		found
+	}
+
+	fn number(&mut self) -> Result<Json, usize>
+	{
+		let start = self.offset;
+		self.skip(b'-');
+		let integer_start = self.offset;
+		match self.digits()
+		{
+			0 => return Err(self.offset),
+			count if count > 1 && self.data[integer_start] == b'0' => return Err(integer_start),
+			_ => {}
+		}
+		if self.skip(b'.') && self.digits() == 0
+		{
+			return Err(self.offset);
+		}
+		if self.skip(b'e') || self.skip(b'E')
+		{
+			if !self.skip(b'+')
+			{
+				self.skip(b'-');
+			}
+			if self.digits() == 0
+			{
+				return Err(self.offset);
+			}
+		}
+		::std::str::from_utf8(&self.data[start..self.offset]).unwrap()
+			.parse()
+			.map(Json::Number)
+			.map_err(|_| start)
+	}
+
+	/// Reads four hexadecimal digits of a unicode escape.
+	fn hex(&mut self) -> Result<u32, usize>
+	{
+		let digit_list = self.data.get(self.offset..self.offset + 4).ok_or(self.offset)?;
+		if !digit_list.iter().all(u8::is_ascii_hexdigit)
+		{
+			return Err(self.offset);
+		}
+		let value = digit_list.iter().fold(0, |value, &digit| value * 16 + (digit as char).to_digit(16).unwrap());
+		self.offset += 4;
+		Ok(value)
+	}
+
+	fn string(&mut self) -> Result<String, usize>
+	{
+		self.expect(b'"')?;
+		let mut bytes = Vec::new();
+		loop
+		{
+			let byte = *self.data.get(self.offset).ok_or(self.offset)?;
+			self.offset += 1;
+			match byte
+			{
+				b'"' => break,
+				byte if byte < 0x20 => return Err(self.offset - 1),
+				b'\\' =>
+				{
+					let escape = *self.data.get(self.offset).ok_or(self.offset)?;
+					self.offset += 1;
+					let character = match escape
+					{
+						b'"' => '"',
+						b'\\' => '\\',
+						b'/' => '/',
+						b'b' => '\u{8}',
+						b'f' => '\u{c}',
+						b'n' => '\n',
+						b'r' => '\r',
+						b't' => '\t',
+						b'u' =>
+						{
+							let start = self.offset - 2;
+							let mut code = self.hex()?;
+							if (0xD800..0xDC00).contains(&code)
+							{
+								// High surrogates are combined with the low surrogate that must follow.
+								if !self.data[self.offset..].starts_with(b"\\u")
+								{
+									return Err(start);
+								}
+								self.offset += 2;
+								let low = self.hex()?;
+								if !(0xDC00..0xE000).contains(&low)
+								{
+									return Err(self.offset - 6);
+								}
+								code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+							}
+							char::from_u32(code).ok_or(start)?
+						}
+						_ => return Err(self.offset - 1),
+					};
+					let mut buffer = [0; 4];
+					bytes.extend_from_slice(character.encode_utf8(&mut buffer).as_bytes());
+				}
+				byte => bytes.push(byte),
+			}
+		}
+		String::from_utf8(bytes).map_err(|_| self.offset)
+	}
+}
@@ -0,0 +1,266 @@
+//! Generated layouts that are kept apart from the rects they were generated for.
+
+mod binary;
+mod export;
+mod import;
+mod json;
+
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+
+use {Atlas, AtlasBin, AtlasRect, OwnedAtlas, RectStore};
+use util::{Rect, Region};
+
+pub use self::binary::{BinaryError, LayoutReader, LayoutWriter, RegionRecord, MAGIC, VERSION};
+pub use self::import::{ImportError, ImportedAtlas, ImportedPage, ImportedSprite, Rotation};
+
+/// How a rect was cropped out of a larger source image, for instance by `util::border_crop`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Trim
+{
+	/// The x-position of the rect inside of the source image.
+	pub x: u32,
+
+	/// The y-position of the rect inside of the source image.
+	pub y: u32,
+
+	/// The width of the source image.
+	pub source_width: u32,
+
+	/// The height of the source image.
+	pub source_height: u32,
+}
+
+impl Trim
+{
+	pub fn new(x: u32, y: u32, source_width: u32, source_height: u32) -> Self
+	{
+		Trim
+		{
+			x,
+			y,
+			source_width,
+			source_height,
+		}
+	}
+
+	/// Returns the trim of a rect that was not cropped.
+	pub fn none(width: u32, height: u32) -> Self
+	{
+		Trim::new(0, 0, width, height)
+	}
+
+	/// Returns true if the rect with the given dimensions was cropped.
+	pub fn is_trimmed(&self, width: u32, height: u32) -> bool
+	{
+		*self != Trim::none(width, height)
+	}
+}
+
+/// An error indicating that a layout could not be applied to a list of rects.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LayoutError
+{
+	/// The layout has the first number of rects, but the second number of rects was given.
+	RectCount(usize, usize),
+
+	/// The rect with the given index has different dimensions than in the layout.
+	RectSize(usize),
+
+	/// A part of the bin with the given index references a rect that is not in the layout.
+	PartIndex(usize),
+
+	/// A part of the bin with the given index does not fit inside of the bin.
+	PartBounds(usize),
+
+	/// The reserved region with the given index belongs to a bin that is not in the layout.
+	ReservedBin(usize),
+
+	/// The alignment of the layout is zero.
+	Alignment,
+}
+
+impl fmt::Display for LayoutError
+{
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+	{
+		match *self
+		{
+			LayoutError::RectCount(expected, found) => write!(f, "layout has {} rects but {} were given", expected, found),
+			LayoutError::RectSize(rect_index) => write!(f, "rect {} does not match the size in the layout", rect_index),
+			LayoutError::PartIndex(bin_index) => write!(f, "bin {} references a rect that is not in the layout", bin_index),
+			LayoutError::PartBounds(bin_index) => write!(f, "bin {} has a part outside of its bounds", bin_index),
+			LayoutError::ReservedBin(reserved_index) => write!(f, "reserved region {} belongs to a bin that is not in the layout", reserved_index),
+			LayoutError::Alignment => write!(f, "layout has an alignment of zero"),
+		}
+	}
+}
+
+impl Error for LayoutError {}
+
+/// The generated bins of an atlas along with the sizes of its rects, but not the rects themselves.
+///
+/// A layout can be saved, for instance with the `serde` feature, and later applied to the same
+/// rects to render images without generating the atlas again.
+///
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AtlasLayout
+{
+	rect_list: Vec<Rect>,
+	bin_list: Vec<AtlasBin>,
+	reserved_list: Vec<(Option<usize>, Region)>,
+	max_bins: Option<usize>,
+	bin_dimensions: Option<Rect>,
+	max_dimensions: Option<Rect>,
+	lower_bound: Option<usize>,
+	alignment: u32,
+	padding: u32,
+}
+
+impl AtlasLayout
+{
+	/// Creates a layout of the bins and rect sizes of the given atlas.
+	pub fn new<T: AtlasRect>(atlas: &Atlas<T>) -> Self
+	{
+		AtlasLayout
+		{
+			rect_list: atlas.rect_list().iter().map(|rect| Rect::new(rect.width(), rect.height())).collect(),
+			bin_list: atlas.bin_list.clone(),
+			reserved_list: atlas.reserved_list.clone(),
+			max_bins: atlas.max_bins,
+			bin_dimensions: atlas.bin_dimensions,
+			max_dimensions: atlas.max_dimensions,
+			lower_bound: atlas.lower_bound,
+			alignment: atlas.alignment,
+			padding: atlas.padding,
+		}
+	}
+
+	/// Creates a layout of the given bins and rect sizes without any constraints, alignment or
+	/// padding.
+	fn from_bins(rect_list: Vec<Rect>, bin_list: Vec<AtlasBin>) -> Self
+	{
+		AtlasLayout
+		{
+			rect_list,
+			bin_list,
+			reserved_list: Vec::new(),
+			max_bins: None,
+			bin_dimensions: None,
+			max_dimensions: None,
+			lower_bound: None,
+			alignment: 1,
+			padding: 0,
+		}
+	}
+
+	/// Returns the sizes of the rects that the layout was generated for.
+	pub fn rect_list(&self) -> &[Rect]
+	{
+		&self.rect_list
+	}
+
+	/// Returns the generated bins.
+	pub fn bin_list(&self) -> &[AtlasBin]
+	{
+		&self.bin_list
+	}
+
+	/// Returns the alignment of every placement and size.
+	pub fn alignment(&self) -> u32
+	{
+		self.alignment
+	}
+
+	/// Returns the padding after every rect.
+	pub fn padding(&self) -> u32
+	{
+		self.padding
+	}
+
+	/// Returns an error if the given rects do not have the sizes of the rects in the layout, or if
+	/// the layout itself is inconsistent, for instance after being edited by hand.
+	pub fn check<T: AtlasRect>(&self, rect_list: &[T]) -> Result<(), LayoutError>
+	{
+		if self.alignment == 0
+		{
+			return Err(LayoutError::Alignment);
+		}
+		if rect_list.len() != self.rect_list.len()
+		{
+			return Err(LayoutError::RectCount(self.rect_list.len(), rect_list.len()));
+		}
+		for (rect_index, (rect, size)) in rect_list.iter().zip(&self.rect_list).enumerate()
+		{
+			if rect.width() != size.width || rect.height() != size.height
+			{
+				return Err(LayoutError::RectSize(rect_index));
+			}
+		}
+		for (bin_index, bin) in self.bin_list.iter().enumerate()
+		{
+			if bin.part_list().iter().any(|part| part.rect_index >= self.rect_list.len())
+			{
+				return Err(LayoutError::PartIndex(bin_index));
+			}
+			let fits = |position: u32, length: u32, limit: u32| position as u64 + length as u64 <= limit as u64;
+			let dimensions = bin.dimensions();
+			for part in bin.part_list()
+			{
+				let size = &self.rect_list[part.rect_index];
+				let (width, height) = if part.rotate { (size.height, size.width) } else { (size.width, size.height) };
+				if !fits(part.x, width, dimensions.width) || !fits(part.y, height, dimensions.height)
+				{
+					return Err(LayoutError::PartBounds(bin_index));
+				}
+			}
+		}
+		for (reserved_index, &(bin_index, _)) in self.reserved_list.iter().enumerate()
+		{
+			if bin_index.is_some_and(|bin_index| bin_index >= self.bin_list.len())
+			{
+				return Err(LayoutError::ReservedBin(reserved_index));
+			}
+		}
+		Ok(())
+	}
+
+	/// Returns an atlas of the given rects with the bins of this layout.
+	///
+	/// The rects must have the same sizes as the rects the layout was generated for.
+	///
+	pub fn apply<'a, T: 'a + AtlasRect>(&self, rect_list: &'a [T]) -> Result<Atlas<'a, T>, LayoutError>
+	{
+		self.check(rect_list)?;
+		Ok(self.atlas(RectStore::Borrowed(rect_list)))
+	}
+
+	/// Returns an atlas owning the given rects with the bins of this layout.
+	///
+	/// The rects must have the same sizes as the rects the layout was generated for.
+	///
+	pub fn apply_owned<T: 'static + AtlasRect>(&self, rect_list: Vec<T>) -> Result<OwnedAtlas<T>, LayoutError>
+	{
+		self.check(&rect_list)?;
+		Ok(self.atlas(RectStore::Shared(Arc::new(rect_list))))
+	}
+
+	fn atlas<'a, T: 'a + AtlasRect>(&self, rect_list: RectStore<'a, T>) -> Atlas<'a, T>
+	{
+		Atlas
+		{
+			rect_list,
+			bin_list: self.bin_list.clone(),
+			reserved_list: self.reserved_list.clone(),
+			max_bins: self.max_bins,
+			bin_dimensions: self.bin_dimensions,
+			max_dimensions: self.max_dimensions,
+			lower_bound: self.lower_bound,
+			alignment: self.alignment,
+			padding: self.padding,
+		}
+	}
+}
@@ -44,11 +44,84 @@
 //! At the heard of `AtlasBuilder` is a `generate` method which takes in an `AtlasGenerator`. The
 //! current recommended generator is the `BinaryTreeGenerator`. You can even call generate multiple
 //! times on the builder to find the best generator that generates the least amount of bins.
+//! Greedy generators such as `BinaryTreeGenerator` can also be given a `gen::SortPolicy`, changing
+//! the order that rects are placed in.
+//! `BinaryTreeGenerator` also takes a `gen::SplitPolicy`, changing how free space is split after
+//! each placement.
+//! Because of these policies, `BinaryTreeGenerator` is no longer a unit struct, so code that used
+//! `BinaryTreeGenerator` as a value must call `BinaryTreeGenerator::new()` or `Default::default()`.
+//! For small atlases such as icon sheets, `OptimalGenerator` searches for the fewest bins possible,
+//! and then for the smallest single bin, within a node or time budget.
+//! `AnnealingGenerator` instead improves a greedy generator by searching for better orders and
+//! rotations of rects over a number of iterations.
 //!
 //! After calling this method, you receive an `Atlas` struct which contains your generated bins. If
 //! you are using the `image` feature, then you can use `Atlas::as_images` to generate a vector of
 //! images corresponding to each generated bin.
 //!
+//! An `Atlas` borrows its rects. To store an atlas, return it from a function or send it to another
+//! thread along with its rects, use `Atlas::build_owned` to give the rects to the builder, or
+//! `Atlas::into_owned` to clone them. The resulting `OwnedAtlas` has every method of `Atlas`,
+//! including image composition, and `Atlas::as_borrowed` converts it back.
+//!
+//! Generation fails with an `AtlasError` when a rect does not fit in a bin or when the limit set by
+//! `AtlasBuilder::max_bins` is reached. The error lists the rects that could not be placed and
+//! still contains the partially generated atlas.
+//!
+//! `Atlas::stats` reports how well the rects were packed. For every bin and for the whole atlas, it
+//! lists the used and wasted pixels, the occupancy, the largest free region and the number of rotated
+//! parts, along with the lower bound on the number of bins.
+//!
+//! To load layouts at runtime, `layout::LayoutWriter` writes a compact, versioned and little-endian
+//! binary layout with the name, bin, position, size, rotation and trim of every rect. At runtime,
+//! `layout::LayoutReader` reads it directly from the bytes, without the `image` feature.
+//!
+//! The writer also writes the metadata of other tools: TexturePacker JSON with `write_texture_packer`
+//! and libGDX `.atlas` files with `write_libgdx`. Going the other way, `layout::ImportedAtlas` reads
+//! both formats, and with the `image` feature, `ImportedAtlas::unpack` cuts existing pages back into
+//! sprites, undoing rotation and restoring trimmed borders, so that old atlases can be packed again.
+//! It reads binary layouts too, and `ImportedAtlas::layout` turns the placed sprites back into an
+//! `layout::AtlasLayout` to keep with `AtlasBuilder::incremental_layout`.
+//!
+//! ## Reserved Regions
+//!
+//! Parts of each bin can be set aside before generating. `AtlasBuilder::reserve` keeps a region free
+//! in every bin, for instance a white pixel used for untextured draws, while `AtlasBuilder::reserve_bin`
+//! only affects a single bin. Rects can also be pinned to a fixed position with `AtlasBuilder::pin`.
+//! Generators pack all remaining rects around these regions, which are listed by
+//! `AtlasBin::reserved_list`.
+//!
+//! When rects are added to an existing atlas, `AtlasBuilder::incremental` keeps unchanged rects at
+//! their previous placements and only places new or changed rects, keeping differences between
+//! generated images small. `AtlasBuilder::incremental_layout` does the same starting from a saved
+//! `layout::AtlasLayout`.
+//!
+//! ## Texture Arrays
+//!
+//! When rendering from texture arrays, every layer must have the same size. Calling
+//! `AtlasBuilder::array` gives every bin the full width and height constraint. `Atlas::rect_uv`
+//! reports the layer of each rect along with its texture coordinates, and `Atlas::as_image_array` and
+//! `Atlas::as_ktx2` write all bins into a single stacked image or KTX2 container.
+//!
+//! ## Mipmaps
+//!
+//! Filtering mipmapped atlases samples larger and larger blocks of pixels, so rects need more room
+//! than a pixel or two of spacing. `AtlasBuilder::mip_levels` derives the alignment and padding needed
+//! to keep rects apart down to the last level. `Atlas::as_mip_chains` then generates every level of
+//! every bin, dilating the edges of each rect into its padding so that neighbours do not bleed.
+//!
+//! ## Runtime Allocation
+//!
+//! Atlases are generated once from a complete list of rects. For rects that come and go at runtime,
+//! such as glyphs, `dynamic::DynamicAtlas` allocates and frees rects one at a time in fixed size pages,
+//! adding pages as needed. `dynamic::AtlasCache` builds on it to cache rects by key, evicting the
+//! least recently used rects when pages are full and reporting the regions that were invalidated.
+//!
+//! When rects arrive one at a time but never leave, such as sprites added in an editor, the packers
+//! in `online` implement `online::OnlinePacker`, placing each rect as it is inserted into the same
+//! `AtlasBin` and `AtlasPart` structures that generators produce. Skyline, shelf and max rects
+//! strategies are provided.
+//!
 //! ## Bins of Bins
 //!
 //! Occasionally, it is also useful to have certain rects together. For instance, in a game you may
@@ -61,9 +134,21 @@
 //! # Creating a Generator
 //!
 //! To create a new generator, create a struct and implement `AtlasGenerator` for it. The
-//! `AtlasGenerator` trait uses dynamic dispatch for instances where a generator can have settings,
-//! for instance multiple heuristic options. `PassthroughGenerator` is an example of a minimal
-//! generator.
+//! `AtlasGenerator` trait takes the generator by reference for instances where a generator can have
+//! settings, for instance multiple heuristic options. `PassthroughGenerator` is an example of a
+//! minimal generator.
+//!
+//! Since `AtlasGenerator` is generic over rects, it cannot be made into a trait object. Every
+//! generator also implements `gen::DynGenerator`, and a `Box<dyn DynGenerator>` is itself a
+//! generator, so generators can be chosen at runtime. `gen::GeneratorRegistry` creates them by name with
+//! parameters, for instance from `"binary_tree:sort=area,split=vertical"` in a config file.
+//!
+//! Generators must be deterministic so that generated atlases are identical across runs and
+//! platforms. Ties between rects are broken by ascending rect index, and randomized generators take
+//! a seed for the provided `util::Rng`.
+//!
+//! Generators whose result depends on the order of rects can also implement `OrderedGenerator`,
+//! which lets `AnnealingGenerator` place rects in an order of its choosing.
 //!
 //! # The `image` Feature
 //!
@@ -76,21 +161,72 @@
 //! ```
 //!
 //! If you keep it enabled, you can create images for generated atlases and gain access to a few
-//! utility functions, such as border cropping.
+//! utility functions, such as border cropping. Images are composed by copying entire rows of RGBA
+//! pixels at once, and `Atlas::as_rgba_images` returns the composed buffers directly.
+//!
+//! # The `rayon` Feature
+//!
+//! The optional `rayon` feature adds parallel versions of the methods that compose bin images, such
+//! as `Atlas::par_as_images`, and `AtlasBuilder::par_generate_best`, which runs generators in
+//! parallel. These require rects and generators to be `Send` and `Sync`. The other methods keep the
+//! same signatures with or without the feature, so enabling it never breaks other users of a crate.
+//!
+//! ```toml
+//! [dependencies.texture_atlas]
+//! features = ["rayon"]
+//! ```
+//!
+//! # The `serde` Feature
+//!
+//! The optional `serde` feature implements `Serialize` and `Deserialize` for rects, regions, parts
+//! and bins, along with `layout::AtlasLayout`. A layout holds the generated bins and the sizes of
+//! the rects, but not the rects themselves, so that it can be saved, loaded later and applied to
+//! the same rects to render images without generating again.
+//!
+//! ```toml
+//! [dependencies.texture_atlas]
+//! features = ["serde"]
+//! ```
 
 #[cfg(feature = "image")]
 extern crate image;
+#[cfg(feature = "rayon")]
+extern crate rayon;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
 
+pub mod dynamic;
 pub mod gen;
+pub mod layout;
+pub mod online;
 pub mod util;
 
+#[cfg(feature = "image")]
 use std::borrow::Borrow;
-use std::cmp::max;
+use std::cmp::{max, min};
+use std::error::Error;
+use std::fmt;
+use std::ops::Deref;
+use std::sync::Arc;
 
 #[cfg(feature = "image")]
-use image::DynamicImage;
+use image::{DynamicImage, RgbaImage};
+
+use layout::AtlasLayout;
+use util::{Rect, Region, RotatableRect};
+
+/// Rounds the given value up to the nearest multiple of the alignment.
+fn align_up(value: u32, alignment: u32) -> u32
+{
+	value.next_multiple_of(alignment)
+}
 
-use util::{Rect, RotatableRect};
+/// Returns the greatest common divisor of both values.
+fn gcd(a: u32, b: u32) -> u32
+{
+	if b == 0 { a } else { gcd(b, a % b) }
+}
 
 /// Represents an axis aligned rectangle to be packed in a bin.
 pub trait AtlasRect
@@ -156,6 +292,8 @@ impl<'a> AtlasRect + 'a
 }
 
 /// References an axis aligned rect placed in a bin by index.
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct AtlasPart
 {
 	/// The index of the original rect list that this class references.
@@ -179,6 +317,11 @@ pub struct AtlasPart
 /// bounding size, capable of encapsulating all objects. Each object should also not pass through
 /// any boundaries and should be disjoint.
 ///
+/// A bin may also contain reserved regions. These are areas that are not backed by any rect but
+/// are still considered occupied, so rects are never placed on top of them.
+///
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct AtlasBin
 {
 	/// The bounding dimensions of the bin.
@@ -186,24 +329,26 @@ pub struct AtlasBin
 
 	/// The list of referenced rects in this bin.
 	part_list: Vec<AtlasPart>,
+
+	/// The list of regions that no rect may occupy.
+	reserved_list: Vec<Region>,
 }
 
 impl AtlasBin
 {
-	/// Initializes a new bin with the given rect at the top right of the bin.
-	fn new(rect_index: usize, dimensions: Rect, rotate: bool) -> Self
+	/// Initializes a new bin of at least the given size containing only the given reserved regions.
+	fn new(mut dimensions: Rect, reserved_list: Vec<Region>) -> Self
 	{
-		let part = AtlasPart
+		for region in &reserved_list
 		{
-			rect_index,
-			x: 0,
-			y: 0,
-			rotate,
-		};
+			dimensions.width = max(dimensions.width, region.right());
+			dimensions.height = max(dimensions.height, region.bottom());
+		}
 		AtlasBin
 		{
 			dimensions,
-			part_list: vec![part],
+			part_list: Vec::new(),
+			reserved_list,
 		}
 	}
 
@@ -219,6 +364,12 @@ impl AtlasBin
 		&self.part_list
 	}
 
+	/// Returns the reserved regions in this bin.
+	pub fn reserved_list(&self) -> &[Region]
+	{
+		&self.reserved_list
+	}
+
 	/// Adds a new rect to the bin. The size of the bin increases if mandatory.
 	fn part_add(&mut self, rect_index: usize, x: u32, y: u32, dimensions: Rect, rotate: bool)
 	{
@@ -242,13 +393,136 @@ impl AsRef<Rect> for AtlasBin
 	}
 }
 
+/// Normalized texture coordinates of a rect placed in a bin.
+#[derive(Copy, Clone, Debug)]
+pub struct AtlasUv
+{
+	/// The index of the bin, which doubles as the layer index when bins form a texture array.
+	pub layer: usize,
+
+	/// The left texture coordinate.
+	pub u_min: f32,
+
+	/// The top texture coordinate.
+	pub v_min: f32,
+
+	/// The right texture coordinate.
+	pub u_max: f32,
+
+	/// The bottom texture coordinate.
+	pub v_max: f32,
+
+	/// Whether the rect is rotated 90 degrees clockwise inside of the coordinates.
+	pub rotate: bool,
+}
+
 /// Generates a texture atlas using a bin packing algorithm.
+///
+/// Generators must be deterministic, generating the same bins for the same rects and constraints
+/// across runs and platforms. Whenever a generator orders rects, ties are broken by ascending rect
+/// index. Randomized generators must take a seed and draw from `util::Rng`.
+///
 pub trait AtlasGenerator
 {
 	/// Generates a list of bins for the given atlas.
 	fn generate<T: AtlasRect>(&self, atlas: &mut Atlas<T>, width: u32, height: u32, rotate: bool);
 }
 
+/// Statistics describing how well a single bin was packed.
+#[derive(Copy, Clone, Debug)]
+pub struct AtlasBinStats
+{
+	/// The number of pixels covered by the rects placed in the bin.
+	pub used_area: u64,
+
+	/// The number of pixels covered by reserved regions of the bin.
+	pub reserved_area: u64,
+
+	/// The number of pixels of the bin that are neither used nor reserved.
+	pub wasted_area: u64,
+
+	/// The ratio of used pixels to the total number of pixels of the bin.
+	pub occupancy: f64,
+
+	/// The largest region of the bin in which no part or reservation lies.
+	pub largest_free: Region,
+
+	/// The number of parts in the bin.
+	pub part_count: usize,
+
+	/// The number of parts in the bin that are rotated.
+	pub rotated_count: usize,
+}
+
+/// Statistics describing how well an atlas was packed.
+///
+/// The per bin statistics are summed for the whole atlas, except for the largest free region which
+/// is the largest of any bin. The number of bins can be compared against a lower bound, which is
+/// only known for atlases generated by a builder.
+///
+#[derive(Clone, Debug)]
+pub struct AtlasStats
+{
+	/// The statistics of every bin.
+	pub bin_list: Vec<AtlasBinStats>,
+
+	/// The total number of pixels of every bin.
+	pub bin_area: u64,
+
+	/// The number of pixels covered by every placed rect.
+	pub used_area: u64,
+
+	/// The number of pixels covered by reserved regions of every bin.
+	pub reserved_area: u64,
+
+	/// The number of pixels of every bin that are neither used nor reserved.
+	pub wasted_area: u64,
+
+	/// The ratio of used pixels to the total number of pixels of every bin.
+	pub occupancy: f64,
+
+	/// The index of the bin with the largest free region, along with that region.
+	pub largest_free: Option<(usize, Region)>,
+
+	/// The number of placed parts.
+	pub part_count: usize,
+
+	/// The number of placed parts that are rotated.
+	pub rotated_count: usize,
+
+	/// The lower bound of bins needed for every rect, as given by `util::lower_bound`.
+	pub lower_bound: Option<usize>,
+}
+
+impl AtlasStats
+{
+	/// Returns the number of bins generated in excess of the lower bound, if it is known.
+	pub fn excess_bins(&self) -> Option<usize>
+	{
+		self.lower_bound.map(|lower_bound| self.bin_list.len().saturating_sub(lower_bound))
+	}
+}
+
+impl fmt::Display for AtlasStats
+{
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+	{
+		for (bin_index, bin) in self.bin_list.iter().enumerate()
+		{
+			writeln!(f, "bin {}: {} parts ({} rotated), {:.1}% occupied, {} pixels wasted, largest free {}x{}",
+				bin_index, bin.part_count, bin.rotated_count, bin.occupancy * 100.0, bin.wasted_area,
+				bin.largest_free.width, bin.largest_free.height)?;
+		}
+		write!(f, "{} bins", self.bin_list.len())?;
+		if let Some(lower_bound) = self.lower_bound
+		{
+			write!(f, " (lower bound {})", lower_bound)?;
+		}
+		write!(f, ": {} parts ({} rotated), {:.1}% occupied, {} pixels wasted",
+			self.part_count, self.rotated_count, self.occupancy * 100.0, self.wasted_area)
+	}
+}
+
 /// List data structure for adding rects.
 ///
 /// This data structure is essentially a wrapper on `Vec<T>` with the difference that it tracks
@@ -303,18 +577,21 @@ impl<T> AtlasRectList<T> where T: AtlasRect
 	}
 
 	/// Returns the lower bound of bins needed for the rects in this list.
+	///
+	/// The bound holds whether or not rotations are allowed. See `util::lower_bound` for a tighter
+	/// bound when rotations are not allowed.
+	///
 	pub fn lower_bound(&self, size: Rect) -> usize
 	{
-		let atlas_rect = &size as &AtlasRect;
-		assert_eq!(atlas_rect.empty(), false);
-		((self.total_area / atlas_rect.area()) + 1) as usize
+		let dimension_list: Vec<Rect> = self.rect_list.iter().map(|rect| (rect as &dyn AtlasRect).dimensions()).collect();
+		util::lower_bound(&dimension_list, size, true)
 	}
 
 	/// Returns an atlas builder using this rect list and given constraints.
-	pub fn build(&self, width: u32, height: u32, rotate: bool) -> AtlasBuilder<T>
+	pub fn build(&self, width: u32, height: u32, rotate: bool) -> AtlasBuilder<'_, T>
 	{
 		let lower_bound = self.lower_bound(Rect::new(width, height));
-		AtlasBuilder::new(&self.rect_list, width, height, rotate, lower_bound)
+		AtlasBuilder::new(RectStore::Borrowed(&self.rect_list), width, height, rotate, lower_bound)
 	}
 }
 
@@ -324,18 +601,42 @@ impl<T> AtlasRectList<T> where T: AtlasRect
 /// generators are not expected to exceed. It also takes a flag indicating whether or not rotations
 /// should be allowed by generators.
 ///
+/// Regions of bins can be reserved ahead of time, for instance to keep a white pixel for untextured
+/// draws, and rects can be pinned to fixed positions. Generators pack the remaining rects around
+/// both.
+///
+/// The number of generated bins can be limited with `max_bins`. Rects that do not fit within the
+/// limit are reported back as an error.
+///
+/// In array mode, every bin has exactly the maximal width and height so that bins can be used as
+/// layers of a texture array.
+///
+/// An alignment can be given so that every placement and size is rounded up to a multiple of it.
+/// This keeps rects from sharing blocks when bins are block compressed. Padding leaves empty space
+/// after every rect, and both can be derived from a number of mipmap levels with `mip_levels`.
+///
+/// Generation can also be incremental. Rects unchanged since a previous atlas keep their previous
+/// placement when it still fits, so that only new or changed rects are placed by the generator.
+///
 pub struct AtlasBuilder<'a, T> where T: 'a + AtlasRect
 {
-	rect_list: &'a [T],
+	rect_list: RectStore<'a, T>,
 	width: u32,
 	height: u32,
 	lower_bound: usize,
 	rotate: bool,
+	max_bins: Option<usize>,
+	array: bool,
+	alignment: u32,
+	padding: u32,
+	reserved_list: Vec<(Option<usize>, Region)>,
+	pin_list: Vec<(usize, AtlasPart)>,
+	keep_list: Vec<(usize, AtlasPart)>,
 }
 
 impl<'a, T> AtlasBuilder<'a, T> where T: 'a + AtlasRect
 {
-	fn new(rect_list: &'a [T], width: u32, height: u32, rotate: bool, lower_bound: usize) -> Self
+	fn new(rect_list: RectStore<'a, T>, width: u32, height: u32, rotate: bool, lower_bound: usize) -> Self
 	{
 		AtlasBuilder
 		{
@@ -343,36 +644,454 @@ impl<'a, T> AtlasBuilder<'a, T> where T: 'a + AtlasRect
 			width,
 			height,
 			lower_bound,
-			rotate
+			rotate,
+			max_bins: None,
+			array: false,
+			alignment: 1,
+			padding: 0,
+			reserved_list: Vec::new(),
+			pin_list: Vec::new(),
+			keep_list: Vec::new(),
+		}
+	}
+
+	/// Limits the number of bins that generators may create.
+	pub fn max_bins(mut self, max_bins: usize) -> Self
+	{
+		self.max_bins = Some(max_bins);
+		self
+	}
+
+	/// Gives every bin the maximal width and height, as layers of a texture array.
+	pub fn array(mut self) -> Self
+	{
+		self.array = true;
+		self
+	}
+
+	/// Rounds every placement and size up to a multiple of the given alignment.
+	pub fn alignment(mut self, alignment: u32) -> Self
+	{
+		assert!(alignment != 0);
+		self.alignment = alignment;
+		self
+	}
+
+	/// Aligns placements to the 4x4 blocks used by block compression formats such as BC1 and ETC2.
+	pub fn block_compressed(self) -> Self
+	{
+		self.alignment(4)
+	}
+
+	/// Leaves at least the given number of empty pixels to the right and bottom of every rect.
+	pub fn padding(mut self, padding: u32) -> Self
+	{
+		self.padding = padding;
+		self
+	}
+
+	/// Aligns and pads rects so that they stay separated down to the last of the given mip levels.
+	///
+	/// The last level samples blocks of `2^(levels - 1)` pixels, so placements are aligned to that
+	/// size. Rects are padded by twice that size so that dilating the edges of neighbouring rects
+	/// into the padding leaves each of them a border of at least one pixel at every level.
+	///
+	/// The number of levels must be between 1 and `util::MAX_MIP_LEVELS`.
+	///
+	pub fn mip_levels(self, levels: u32) -> Self
+	{
+		assert!(levels != 0 && levels <= util::MAX_MIP_LEVELS);
+		let block = 1 << (levels - 1);
+		let alignment = self.alignment / gcd(self.alignment, block) * block;
+		let padding = max(self.padding, block * 2);
+		self.alignment(alignment).padding(padding)
+	}
+
+	/// Reserves the given region in every bin.
+	pub fn reserve(mut self, x: u32, y: u32, width: u32, height: u32) -> Self
+	{
+		self.reserved_list.push((None, Region::new(x, y, width, height)));
+		self
+	}
+
+	/// Reserves the given region in the indicated bin only.
+	pub fn reserve_bin(mut self, bin_index: usize, x: u32, y: u32, width: u32, height: u32) -> Self
+	{
+		self.reserved_list.push((Some(bin_index), Region::new(x, y, width, height)));
+		self
+	}
+
+	/// Places the indicated rect at a fixed position in the indicated bin.
+	///
+	/// Pinned rects are placed before the generator runs. It is up to the caller to make sure that
+	/// pinned rects do not overlap each other or any reserved regions. Rects pinned to a bin past
+	/// `max_bins` are not placed at all and are reported as unplaced.
+	///
+	pub fn pin(mut self, rect_index: usize, bin_index: usize, x: u32, y: u32, rotate: bool) -> Self
+	{
+		self.pin_list.push((bin_index, AtlasPart
+		{
+			rect_index,
+			x,
+			y,
+			rotate,
+		}));
+		self
+	}
+
+	/// Keeps the placements of rects that are unchanged since the given previous atlas.
+	///
+	/// The index list maps each rect of this builder to the index of the same rect in the previous
+	/// atlas, or `None` if the rect is new. A rect is unchanged if it has the same dimensions as
+	/// before. Its previous placement is kept if it is still aligned, within the size constraints,
+	/// only rotated if rotation is allowed and does not overlap reserved regions, pinned rects or
+	/// other kept rects. All other rects, including those mapped to an index that is not in the
+	/// previous atlas, are placed by the generator into the remaining space.
+	///
+	pub fn incremental<U>(self, previous: &Atlas<U>, previous_index_list: &[Option<usize>]) -> Self
+		where U: AtlasRect
+	{
+		self.incremental_from(&previous.bin_list, |previous_index|
+		{
+			previous.rect_list.get(previous_index).map(|rect| (rect as &dyn AtlasRect).dimensions())
+		}, previous_index_list)
+	}
+
+	/// Keeps the placements of rects that are unchanged since the given saved layout, for instance
+	/// one loaded from JSON with the `serde` feature.
+	///
+	/// The index list maps rects to the rects of the layout like in `incremental`.
+	///
+	pub fn incremental_layout(self, previous: &AtlasLayout, previous_index_list: &[Option<usize>]) -> Self
+	{
+		self.incremental_from(previous.bin_list(), |previous_index| previous.rect_list().get(previous_index).cloned(),
+			previous_index_list)
+	}
+
+	/// Keeps the placements of unchanged rects from the given previous bins, where the dimensions of
+	/// previous rects are given by index.
+	fn incremental_from<F>(mut self, bin_list: &[AtlasBin], previous_dimensions: F, previous_index_list: &[Option<usize>]) -> Self
+		where F: Fn(usize) -> Option<Rect>
+	{
+		for (rect_index, &previous_index) in previous_index_list.iter().enumerate().take(self.rect_list.len())
+		{
+			let (previous_index, previous_dimensions) = match previous_index.and_then(|index| previous_dimensions(index).map(|dimensions| (index, dimensions)))
+			{
+				Some(previous) => previous,
+				None => continue,
+			};
+			let dimensions = (&self.rect_list[rect_index] as &dyn AtlasRect).dimensions();
+			if dimensions.width != previous_dimensions.width || dimensions.height != previous_dimensions.height
+			{
+				continue;
+			}
+			let previous_part = bin_list.iter().enumerate().filter_map(|(bin_index, bin)|
+			{
+				bin.part_list.iter().find(|part| part.rect_index == previous_index).map(|part| (bin_index, part))
+			}).next();
+			if let Some((bin_index, part)) = previous_part
+			{
+				self.keep_list.push((bin_index, AtlasPart
+				{
+					rect_index,
+					x: part.x,
+					y: part.y,
+					rotate: part.rotate,
+				}));
+			}
 		}
+		self
 	}
 
 	/// Generates bins using the given generator.
-	pub fn generate<G: AtlasGenerator>(self, generator: &G) -> Atlas<'a, T>
+	///
+	/// Returns an error containing the partially generated atlas if any rect could not be placed,
+	/// either because it is larger than a bin or because the bin limit was reached.
+	///
+	pub fn generate<G: AtlasGenerator>(self, generator: &G) -> Result<Atlas<'a, T>, AtlasError<'a, T>>
+	{
+		self.generate_trial(generator)
+	}
+
+	/// Generates bins using each of the given generators and returns the best result.
+	///
+	/// The best atlas has the fewest bins, then the smallest last bin, then comes first in the list.
+	/// If every generator fails, the error with the fewest unplaced rects is returned.
+	///
+	pub fn generate_best<G: AtlasGenerator>(self, generator_list: &[G]) -> Result<Atlas<'a, T>, AtlasError<'a, T>>
+	{
+		assert!(!generator_list.is_empty());
+		AtlasBuilder::result_best(generator_list.iter().map(|generator| self.generate_trial(generator)).collect())
+	}
+
+	/// Generates bins using each of the given generators in parallel and returns the best result,
+	/// like `generate_best`.
+	#[cfg(feature = "rayon")]
+	pub fn par_generate_best<G>(self, generator_list: &[G]) -> Result<Atlas<'a, T>, AtlasError<'a, T>>
+		where G: AtlasGenerator + Sync, T: Send + Sync
+	{
+		assert!(!generator_list.is_empty());
+		AtlasBuilder::result_best(util::par_map_list(generator_list, |generator| self.generate_trial(generator)))
+	}
+
+	/// Returns the best of the given results, as described by `generate_best`.
+	fn result_best(result_list: Vec<Result<Atlas<'a, T>, AtlasError<'a, T>>>) -> Result<Atlas<'a, T>, AtlasError<'a, T>>
+	{
+		let mut best: Option<Result<Atlas<'a, T>, AtlasError<'a, T>>> = None;
+		for result in result_list
+		{
+			let better = match (&best, &result)
+			{
+				(None, _) => true,
+				(Some(Ok(best_atlas)), Ok(atlas)) => atlas.score() < best_atlas.score(),
+				(Some(Ok(_)), Err(_)) => false,
+				(Some(Err(_)), Ok(_)) => true,
+				(Some(Err(best_error)), Err(error)) =>
+				{
+					error.unplaced_list.len() < best_error.unplaced_list.len()
+				}
+			};
+			if better
+			{
+				best = Some(result);
+			}
+		}
+		best.unwrap()
+	}
+
+	/// Generates bins using the given generator without consuming the builder.
+	fn generate_trial<G: AtlasGenerator>(&self, generator: &G) -> Result<Atlas<'a, T>, AtlasError<'a, T>>
 	{
 		let mut atlas = Atlas
 		{
-			rect_list: self.rect_list,
-			bin_list: Vec::with_capacity(self.lower_bound),
+			rect_list: self.rect_list.clone(),
+			bin_list: Vec::new(),
+			reserved_list: self.reserved_list.clone(),
+			max_bins: self.max_bins,
+			bin_dimensions: if self.array { Some(Rect::new(self.width, self.height)) } else { None },
+			max_dimensions: Some(Rect::new(self.width, self.height)),
+			lower_bound: None,
+			alignment: self.alignment,
+			padding: self.padding,
 		};
+		if self.width != 0 && self.height != 0
+		{
+			let dimension_list: Vec<Rect> = (0..self.rect_list.len())
+				.map(|rect_index| atlas.rect_dimensions(rect_index, false))
+				.collect();
+			let lower_bound = util::lower_bound(&dimension_list, Rect::new(self.width, self.height), self.rotate);
+			let capacity = max(self.lower_bound, lower_bound);
+			atlas.bin_list.reserve(self.max_bins.map_or(capacity, |max_bins| min(capacity, max_bins)));
+			atlas.lower_bound = Some(lower_bound);
+		}
+		let mut rejected_list = Vec::new();
+		for &(bin_index, ref part) in &self.pin_list
+		{
+			if self.max_bins.is_some_and(|max_bins| bin_index >= max_bins)
+			{
+				rejected_list.push(part.rect_index);
+				continue;
+			}
+			while atlas.bin_list.len() <= bin_index
+			{
+				atlas.bin_add_empty();
+			}
+			atlas.bin_add_rect(bin_index, part.rect_index, part.x, part.y, part.rotate);
+		}
+		let mut placed_list = atlas.rect_placed_list();
+		for &(bin_index, ref part) in &self.keep_list
+		{
+			let dimensions = atlas.rect_dimensions(part.rect_index, part.rotate);
+			let region = Region::new(part.x, part.y, dimensions.width, dimensions.height);
+			let occupied_list = if bin_index < atlas.bin_list.len()
+			{
+				atlas.bin_occupied_list(bin_index)
+			}
+			else
+			{
+				atlas.bin_reserved_list(bin_index)
+			};
+
+			let fits = region.right() <= self.width && region.bottom() <= self.height &&
+				part.x % self.alignment == 0 && part.y % self.alignment == 0 &&
+				(self.rotate || !part.rotate) &&
+				self.max_bins.is_none_or(|max_bins| bin_index < max_bins) &&
+				!occupied_list.iter().any(|occupied| occupied.intersects(&region));
+			if fits && !placed_list[part.rect_index]
+			{
+				while atlas.bin_list.len() <= bin_index
+				{
+					atlas.bin_add_empty();
+				}
+				atlas.bin_add_rect(bin_index, part.rect_index, part.x, part.y, part.rotate);
+				placed_list[part.rect_index] = true;
+			}
+		}
 		generator.generate(&mut atlas, self.width, self.height, self.rotate);
-		atlas
+
+		// Rejected pins may have been placed by the generator, but they must stay unplaced.
+		for bin in &mut atlas.bin_list
+		{
+			bin.part_list.retain(|part| !rejected_list.contains(&part.rect_index));
+		}
+
+		let unplaced_list: Vec<usize> = atlas.rect_placed_list().iter()
+			.enumerate()
+			.filter(|&(_, &placed)| !placed)
+			.map(|(rect_index, _)| rect_index)
+			.collect();
+		if unplaced_list.is_empty()
+		{
+			Ok(atlas)
+		}
+		else
+		{
+			Err(AtlasError
+			{
+				atlas: Box::new(atlas),
+				unplaced_list,
+			})
+		}
+	}
+}
+
+/// An error indicating that generation could not place every rect.
+///
+/// The error keeps the partially generated atlas so that tooling can still inspect or render it
+/// and report which rects need to be cut.
+///
+pub struct AtlasError<'a, T: 'a + AtlasRect>
+{
+	atlas: Box<Atlas<'a, T>>,
+	unplaced_list: Vec<usize>,
+}
+
+impl<'a, T> AtlasError<'a, T> where T: 'a + AtlasRect
+{
+	/// Returns the partially generated atlas.
+	pub fn atlas(&self) -> &Atlas<'a, T>
+	{
+		&self.atlas
+	}
+
+	/// Returns the partially generated atlas, consuming the error.
+	pub fn into_atlas(self) -> Atlas<'a, T>
+	{
+		*self.atlas
+	}
+
+	/// Returns the indices of the rects that could not be placed.
+	pub fn unplaced_list(&self) -> &[usize]
+	{
+		&self.unplaced_list
+	}
+}
+
+impl<'a, T> fmt::Debug for AtlasError<'a, T> where T: 'a + AtlasRect
+{
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+	{
+		f.debug_struct("AtlasError")
+			.field("bin_count", &self.atlas.bin_list.len())
+			.field("unplaced_list", &self.unplaced_list)
+			.finish()
+	}
+}
+
+impl<'a, T> fmt::Display for AtlasError<'a, T> where T: 'a + AtlasRect
+{
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+	{
+		write!(f, "{} rects could not be placed in {} bins", self.unplaced_list.len(), self.atlas.bin_list.len())
+	}
+}
+
+impl<'a, T> Error for AtlasError<'a, T> where T: 'a + AtlasRect {}
+
+/// The rects of an atlas, either borrowed or shared by owned atlases.
+enum RectStore<'a, T: 'a>
+{
+	Borrowed(&'a [T]),
+	Shared(Arc<Vec<T>>),
+}
+
+impl<'a, T> Clone for RectStore<'a, T> where T: 'a
+{
+	fn clone(&self) -> Self
+	{
+		match *self
+		{
+			RectStore::Borrowed(rect_list) => RectStore::Borrowed(rect_list),
+			RectStore::Shared(ref rect_list) => RectStore::Shared(rect_list.clone()),
+		}
+	}
+}
+
+impl<'a, T> Deref for RectStore<'a, T> where T: 'a
+{
+	type Target = [T];
+
+	fn deref(&self) -> &[T]
+	{
+		match *self
+		{
+			RectStore::Borrowed(rect_list) => rect_list,
+			RectStore::Shared(ref rect_list) => rect_list,
+		}
 	}
 }
 
 /// Encapsulates axis aligned rectangles and generated bins.
+///
+/// An atlas either borrows its rects or owns them, in which case it is an `OwnedAtlas`. Both kinds
+/// of atlas support the same methods.
+///
 pub struct Atlas<'a, T: 'a + AtlasRect>
 {
-	rect_list: &'a [T],
+	rect_list: RectStore<'a, T>,
 	bin_list: Vec<AtlasBin>,
+	reserved_list: Vec<(Option<usize>, Region)>,
+	max_bins: Option<usize>,
+	bin_dimensions: Option<Rect>,
+	max_dimensions: Option<Rect>,
+	lower_bound: Option<usize>,
+	alignment: u32,
+	padding: u32,
+}
+
+/// An atlas that owns its rects, so that it can be stored, returned or sent to another thread along
+/// with them.
+///
+/// Owned atlases are created with `Atlas::build_owned` or `Atlas::into_owned`, and converted back to
+/// a borrowing atlas with `Atlas::as_borrowed`. Cloning an owned atlas shares its rects.
+///
+pub type OwnedAtlas<T> = Atlas<'static, T>;
+
+impl<'a, T> Clone for Atlas<'a, T> where T: 'a + AtlasRect
+{
+	fn clone(&self) -> Self
+	{
+		Atlas
+		{
+			rect_list: self.rect_list.clone(),
+			bin_list: self.bin_list.clone(),
+			reserved_list: self.reserved_list.clone(),
+			max_bins: self.max_bins,
+			bin_dimensions: self.bin_dimensions,
+			max_dimensions: self.max_dimensions,
+			lower_bound: self.lower_bound,
+			alignment: self.alignment,
+			padding: self.padding,
+		}
+	}
 }
 
 impl<'a, T> Atlas<'a, T> where T: 'a + AtlasRect
 {
 	/// Returns a builder instance with the given size constraints.
-	pub fn build(rect_list: &'a [T], width: u32, height: u32, rotate: bool) -> AtlasBuilder<T>
+	pub fn build(rect_list: &'a [T], width: u32, height: u32, rotate: bool) -> AtlasBuilder<'a, T>
 	{
-		AtlasBuilder::new(rect_list, width, height, rotate, 1)
+		AtlasBuilder::new(RectStore::Borrowed(rect_list), width, height, rotate, 1)
 	}
 
 	/// Creates a new atlas with the given axis-aligned rectangles.
@@ -380,11 +1099,86 @@ impl<'a, T> Atlas<'a, T> where T: 'a + AtlasRect
 	{
 		Self
 		{
-			rect_list,
+			rect_list: RectStore::Borrowed(rect_list),
 			bin_list: Vec::new(),
+			reserved_list: Vec::new(),
+			max_bins: None,
+			bin_dimensions: None,
+			max_dimensions: None,
+			lower_bound: None,
+			alignment: 1,
+			padding: 0,
+		}
+	}
+
+	/// Returns an atlas of the given rects with the same bins and settings as this atlas.
+	pub(crate) fn with_rect_list<'b, U: 'b + AtlasRect>(&self, rect_list: &'b [U]) -> Atlas<'b, U>
+	{
+		Atlas
+		{
+			rect_list: RectStore::Borrowed(rect_list),
+			bin_list: self.bin_list.clone(),
+			reserved_list: self.reserved_list.clone(),
+			max_bins: self.max_bins,
+			bin_dimensions: self.bin_dimensions,
+			max_dimensions: self.max_dimensions,
+			lower_bound: self.lower_bound,
+			alignment: self.alignment,
+			padding: self.padding,
+		}
+	}
+
+	/// Returns this atlas with its rects replaced by the given rects.
+	fn into_store<'b>(self, rect_list: RectStore<'b, T>) -> Atlas<'b, T>
+	{
+		let Atlas { bin_list, reserved_list, max_bins, bin_dimensions, max_dimensions, lower_bound, alignment, padding, .. } = self;
+		Atlas
+		{
+			rect_list,
+			bin_list,
+			reserved_list,
+			max_bins,
+			bin_dimensions,
+			max_dimensions,
+			lower_bound,
+			alignment,
+			padding,
+		}
+	}
+
+	/// Returns an atlas that owns its rects, cloning them if they are borrowed.
+	pub fn into_owned(self) -> OwnedAtlas<T> where T: Clone + 'static
+	{
+		let rect_list = match self.rect_list
+		{
+			RectStore::Borrowed(rect_list) => Arc::new(rect_list.to_vec()),
+			RectStore::Shared(ref rect_list) => rect_list.clone(),
+		};
+		self.into_store(RectStore::Shared(rect_list))
+	}
+
+	/// Returns an atlas with the same bins that borrows the rects of this atlas.
+	pub fn as_borrowed(&self) -> Atlas<'_, T>
+	{
+		self.clone().into_store(RectStore::Borrowed(&self.rect_list))
+	}
+
+	/// Returns true if the atlas owns its rects.
+	pub fn is_owned(&self) -> bool
+	{
+		match self.rect_list
+		{
+			RectStore::Borrowed(_) => false,
+			RectStore::Shared(_) => true,
 		}
 	}
 
+	/// Returns the bins and rect sizes of this atlas without the rects themselves.
+	pub fn layout(&self) -> AtlasLayout
+	{
+		AtlasLayout::new(self)
+	}
+
 	/// Returns the list of axis-aligned rectangles that are part of the atlas.
 	pub fn rect_list(&self) -> &[T]
 	{
@@ -397,19 +1191,254 @@ impl<'a, T> Atlas<'a, T> where T: 'a + AtlasRect
 		&self.bin_list
 	}
 
+	/// Returns the maximal number of bins generators are allowed to create, if limited.
+	pub fn max_bins(&self) -> Option<usize>
+	{
+		self.max_bins
+	}
+
+	/// Returns the dimensions shared by every bin if the atlas was built in array mode.
+	pub fn bin_dimensions(&self) -> Option<Rect>
+	{
+		self.bin_dimensions
+	}
+
+	/// Returns the maximal dimensions of bins if the atlas was generated by a builder.
+	pub fn max_dimensions(&self) -> Option<Rect>
+	{
+		self.max_dimensions
+	}
+
+	/// Returns the lower bound of the number of bins, if the atlas was generated by a builder.
+	///
+	/// The bound is computed from the space rects take up, including padding and alignment.
+	///
+	pub fn lower_bound(&self) -> Option<usize>
+	{
+		self.lower_bound
+	}
+
+	/// Returns the alignment that placements and sizes are rounded up to.
+	pub fn alignment(&self) -> u32
+	{
+		self.alignment
+	}
+
+	/// Returns the number of empty pixels kept to the right and bottom of every rect.
+	pub fn padding(&self) -> u32
+	{
+		self.padding
+	}
+
+	/// Returns the space the indicated rect takes up in a bin, which generators should pack.
+	///
+	/// This is the rect's dimensions, rotated if requested, padded and then rounded up to the
+	/// alignment.
+	///
+	pub fn rect_dimensions(&self, rect_index: usize, rotate: bool) -> Rect
+	{
+		let dimensions = (&self.rect_list[rect_index] as &dyn AtlasRect).dimensions_rotated(rotate);
+		let width = align_up(dimensions.width + self.padding, self.alignment);
+		let height = align_up(dimensions.height + self.padding, self.alignment);
+		Rect::new(width, height)
+	}
+
+	/// Returns the regions of the indicated bin covered by the contents of its parts.
+	pub fn bin_content_list(&self, bin_index: usize) -> Vec<Region>
+	{
+		self.bin_list[bin_index].part_list.iter().map(|part|
+		{
+			let dimensions = (&self.rect_list[part.rect_index] as &dyn AtlasRect).dimensions_rotated(part.rotate);
+			Region::new(part.x, part.y, dimensions.width, dimensions.height)
+		}).collect()
+	}
+
+	/// Returns the index of the bin containing the indicated rect and the part referencing it.
+	pub fn rect_part(&self, rect_index: usize) -> Option<(usize, &AtlasPart)>
+	{
+		for (bin_index, bin) in self.bin_list.iter().enumerate()
+		{
+			if let Some(part) = bin.part_list.iter().find(|part| part.rect_index == rect_index)
+			{
+				return Some((bin_index, part));
+			}
+		}
+		None
+	}
+
+	/// Returns the normalized texture coordinates of the indicated rect, if it was placed.
+	pub fn rect_uv(&self, rect_index: usize) -> Option<AtlasUv>
+	{
+		self.rect_part(rect_index).map(|(bin_index, part)|
+		{
+			let bin_dimensions = self.bin_list[bin_index].dimensions;
+			let dimensions = (&self.rect_list[rect_index] as &dyn AtlasRect).dimensions_rotated(part.rotate);
+			let width = bin_dimensions.width as f32;
+			let height = bin_dimensions.height as f32;
+			AtlasUv
+			{
+				layer: bin_index,
+				u_min: part.x as f32 / width,
+				v_min: part.y as f32 / height,
+				u_max: (part.x + dimensions.width) as f32 / width,
+				v_max: (part.y + dimensions.height) as f32 / height,
+				rotate: part.rotate,
+			}
+		})
+	}
+
+	/// Expands the given region outwards to the alignment.
+	fn region_aligned(&self, region: &Region) -> Region
+	{
+		let x = region.x / self.alignment * self.alignment;
+		let y = region.y / self.alignment * self.alignment;
+		let right = align_up(region.right(), self.alignment);
+		let bottom = align_up(region.bottom(), self.alignment);
+		Region::new(x, y, right - x, bottom - y)
+	}
+
+	/// Returns the reserved regions that apply to the indicated bin, without alignment.
+	fn reserved_list_bin(&self, bin_index: usize) -> Vec<Region>
+	{
+		self.reserved_list.iter()
+			.filter(|&&(reserved_bin, _)| reserved_bin.is_none_or(|index| index == bin_index))
+			.map(|&(_, region)| region)
+			.collect()
+	}
+
+	/// Returns the regions reserved in the indicated bin, even if the bin does not exist yet.
+	///
+	/// Regions are expanded outwards to the alignment.
+	///
+	pub fn bin_reserved_list(&self, bin_index: usize) -> Vec<Region>
+	{
+		self.reserved_list_bin(bin_index).iter().map(|region| self.region_aligned(region)).collect()
+	}
+
+	/// Returns the regions of the indicated bin that are taken up by either parts or reservations.
+	///
+	/// Regions are expanded outwards to the alignment.
+	///
+	pub fn bin_occupied_list(&self, bin_index: usize) -> Vec<Region>
+	{
+		let bin = &self.bin_list[bin_index];
+		let mut region_list = bin.reserved_list.clone();
+		for part in &bin.part_list
+		{
+			let dimensions = self.rect_dimensions(part.rect_index, part.rotate);
+			region_list.push(Region::new(part.x, part.y, dimensions.width, dimensions.height));
+		}
+		region_list.iter().map(|region| self.region_aligned(region)).collect()
+	}
+
+	/// Returns statistics describing how well the indicated bin was packed.
+	pub fn bin_stats(&self, bin_index: usize) -> AtlasBinStats
+	{
+		let bin = &self.bin_list[bin_index];
+		let used_area = bin.part_list.iter().map(|part| (&self.rect_list[part.rect_index] as &dyn AtlasRect).area()).sum();
+		let reserved_area = bin.reserved_list.iter().map(|region| (region as &dyn AtlasRect).area()).sum();
+		let bin_area = (bin as &dyn AtlasRect).area();
+		AtlasBinStats
+		{
+			used_area,
+			reserved_area,
+			wasted_area: bin_area.saturating_sub(used_area + reserved_area),
+			occupancy: if bin_area == 0 { 0.0 } else { used_area as f64 / bin_area as f64 },
+			largest_free: util::region_largest_free(bin.dimensions, &self.bin_occupied_list(bin_index)),
+			part_count: bin.part_list.len(),
+			rotated_count: bin.part_list.iter().filter(|part| part.rotate).count(),
+		}
+	}
+
+	/// Returns statistics describing how well every bin was packed.
+	pub fn stats(&self) -> AtlasStats
+	{
+		let bin_list: Vec<AtlasBinStats> = (0..self.bin_list.len()).map(|bin_index| self.bin_stats(bin_index)).collect();
+		let bin_area = self.bin_list.iter().map(|bin| (bin as &dyn AtlasRect).area()).sum();
+		let used_area = bin_list.iter().map(|bin| bin.used_area).sum();
+		let mut largest_free: Option<(usize, Region)> = None;
+		for (bin_index, bin) in bin_list.iter().enumerate()
+		{
+			let area = (&bin.largest_free as &dyn AtlasRect).area();
+			if area != 0 && largest_free.is_none_or(|(_, region)| area > (&region as &dyn AtlasRect).area())
+			{
+				largest_free = Some((bin_index, bin.largest_free));
+			}
+		}
+		AtlasStats
+		{
+			bin_area,
+			used_area,
+			reserved_area: bin_list.iter().map(|bin| bin.reserved_area).sum(),
+			wasted_area: bin_list.iter().map(|bin| bin.wasted_area).sum(),
+			occupancy: if bin_area == 0 { 0.0 } else { used_area as f64 / bin_area as f64 },
+			largest_free,
+			part_count: bin_list.iter().map(|bin| bin.part_count).sum(),
+			rotated_count: bin_list.iter().map(|bin| bin.rotated_count).sum(),
+			lower_bound: self.lower_bound,
+			bin_list,
+		}
+	}
+
+	/// Returns the number of bins followed by the area of the last bin, where lower is better.
+	fn score(&self) -> (usize, u64)
+	{
+		let area = self.bin_list.last().map_or(0, |bin| (bin as &dyn AtlasRect).area());
+		(self.bin_list.len(), area)
+	}
+
+	/// Returns a list indicating which rects have already been placed in a bin.
+	///
+	/// Generators should skip placed rects, since they may have been pinned by the builder.
+	///
+	pub fn rect_placed_list(&self) -> Vec<bool>
+	{
+		let mut placed_list = vec![false; self.rect_list.len()];
+		for bin in &self.bin_list
+		{
+			for part in &bin.part_list
+			{
+				placed_list[part.rect_index] = true;
+			}
+		}
+		placed_list
+	}
+
+	/// Returns true if generators are allowed to create another bin.
+	pub fn bin_can_add(&self) -> bool
+	{
+		self.max_bins.is_none_or(|max_bins| self.bin_list.len() < max_bins)
+	}
+
+	/// Creates a new bin containing only its reserved regions.
+	pub fn bin_add_empty(&mut self) -> usize
+	{
+		let bin_index = self.bin_list.len();
+		let reserved_list = self.reserved_list_bin(bin_index);
+		let dimensions = self.bin_dimensions.unwrap_or(Rect::new(0, 0));
+		self.bin_list.push(AtlasBin::new(dimensions, reserved_list));
+		bin_index
+	}
+
 	/// Creates a new bin with the given rect at the top left.
+	///
+	/// The rect is placed regardless of reserved regions. Generators that respect reservations
+	/// should use `bin_add_empty` instead.
+	///
 	pub fn bin_add_new(&mut self, rect_index: usize, rotate: bool) -> usize
 	{
-		let bin_index = self.bin_list.len();
-		let dimensions = (&self.rect_list[rect_index] as &AtlasRect).dimensions_rotated(rotate);
-		self.bin_list.push(AtlasBin::new(rect_index, dimensions, rotate));
+		let bin_index = self.bin_add_empty();
+		self.bin_add_rect(bin_index, rect_index, 0, 0, rotate);
 		bin_index
 	}
 
 	/// Adds a new rect to the indicated bin.
+	///
+	/// The bin grows to fit the aligned size of the rect.
+	///
 	pub fn bin_add_rect(&mut self, bin_index: usize, rect_index: usize, x: u32, y: u32, rotate: bool)
 	{
-		let dimensions = (&self.rect_list[rect_index] as &AtlasRect).dimensions_rotated(rotate);
+		let dimensions = self.rect_dimensions(rect_index, rotate);
 		self.bin_list[bin_index].part_add(rect_index, x, y, dimensions, rotate);
 	}
 
@@ -418,7 +1447,7 @@ impl<'a, T> Atlas<'a, T> where T: 'a + AtlasRect
 	pub fn bin_as_colors(&self, bin_index: usize) -> DynamicImage
 	{
 		let weight = util::colors_weight(self.rect_list.len());
-		util::colors_from_bin(weight, self.rect_list, &self.bin_list[bin_index])
+		util::colors_from_bin(weight, &self.rect_list, &self.bin_list[bin_index])
 	}
 
 	#[cfg(feature = "image")]
@@ -426,13 +1455,30 @@ impl<'a, T> Atlas<'a, T> where T: 'a + AtlasRect
 	pub fn as_colors(&self) -> Vec<DynamicImage>
 	{
 		let weight = util::colors_weight(self.rect_list.len());
-		let mut image_list = Vec::with_capacity(self.rect_list.len());
+		self.bin_list.iter().map(|bin| util::colors_from_bin(weight, &self.rect_list, bin)).collect()
+	}
 
-		for bin in &self.bin_list
-		{
-			image_list.push(util::colors_from_bin(weight, self.rect_list, bin));
-		}
-		image_list
+	#[cfg(all(feature = "image", feature = "rayon"))]
+	/// Generates images from the generated bins with uniformly separated colors, in parallel.
+	pub fn par_as_colors(&self) -> Vec<DynamicImage> where T: Send + Sync
+	{
+		let weight = util::colors_weight(self.rect_list.len());
+		util::par_map_list(&self.bin_list, |bin| util::colors_from_bin(weight, &self.rect_list, bin))
+	}
+}
+
+impl<T> Atlas<'static, T> where T: 'static + AtlasRect
+{
+	/// Returns a builder that takes ownership of the given rects and generates an `OwnedAtlas`.
+	pub fn build_owned(rect_list: Vec<T>, width: u32, height: u32, rotate: bool) -> AtlasBuilder<'static, T>
+	{
+		AtlasBuilder::new(RectStore::Shared(Arc::new(rect_list)), width, height, rotate, 1)
+	}
+
+	/// Creates a new atlas owning the given axis-aligned rectangles.
+	pub fn new_owned(rect_list: Vec<T>) -> Self
+	{
+		Atlas::new(&[]).into_store(RectStore::Shared(Arc::new(rect_list)))
 	}
 }
 
@@ -442,18 +1488,85 @@ impl<'a, T> Atlas<'a, T> where T: AtlasRect + Borrow<DynamicImage>
 	/// Returns the given bin as an image.
 	pub fn bin_as_image(&self, bin_index: usize) -> DynamicImage
 	{
-		util::image_from_bin(self.rect_list, &self.bin_list[bin_index])
+		util::image_from_bin(&self.rect_list, &self.bin_list[bin_index])
 	}
 
 	/// Generates images from the generated bin using the given image objects.
 	pub fn as_images(&self) -> Vec<DynamicImage>
 	{
-		let mut image_list = Vec::with_capacity(self.rect_list.len());
+		self.bin_list.iter().map(|bin| util::image_from_bin(&self.rect_list, bin)).collect()
+	}
 
-		for bin in &self.bin_list
-		{
-			image_list.push(util::image_from_bin(self.rect_list, bin));
-		}
-		image_list
+	/// Generates images from the generated bins using the given image objects, in parallel.
+	#[cfg(feature = "rayon")]
+	pub fn par_as_images(&self) -> Vec<DynamicImage> where T: Send + Sync
+	{
+		util::par_map_list(&self.bin_list, |bin| util::image_from_bin(&self.rect_list, bin))
+	}
+
+	/// Returns the given bin as an RGBA buffer.
+	pub fn bin_as_rgba(&self, bin_index: usize) -> RgbaImage
+	{
+		util::rgba_from_bin(&self.rect_list, &self.bin_list[bin_index])
+	}
+
+	/// Generates RGBA buffers from the generated bins using the given image objects.
+	///
+	/// This is the same as `as_images` without wrapping each buffer in a `DynamicImage`.
+	///
+	pub fn as_rgba_images(&self) -> Vec<RgbaImage>
+	{
+		self.bin_list.iter().map(|bin| util::rgba_from_bin(&self.rect_list, bin)).collect()
+	}
+
+	/// Generates RGBA buffers from the generated bins using the given image objects, in parallel.
+	#[cfg(feature = "rayon")]
+	pub fn par_as_rgba_images(&self) -> Vec<RgbaImage> where T: Send + Sync
+	{
+		util::par_map_list(&self.bin_list, |bin| util::rgba_from_bin(&self.rect_list, bin))
+	}
+
+	/// Generates the mip chain of every bin, starting with the full size image.
+	///
+	/// At every level, the edge colors of each rect are dilated into the surrounding padding so that
+	/// filtering does not bleed neighbouring rects into each other.
+	///
+	pub fn as_mip_chains(&self, levels: u32) -> Vec<Vec<DynamicImage>>
+	{
+		(0..self.bin_list.len()).map(|bin_index| self.bin_as_mip_chain(bin_index, levels)).collect()
+	}
+
+	/// Generates the mip chain of every bin in parallel, like `as_mip_chains`.
+	#[cfg(feature = "rayon")]
+	pub fn par_as_mip_chains(&self, levels: u32) -> Vec<Vec<DynamicImage>> where T: Send + Sync
+	{
+		let bin_index_list: Vec<usize> = (0..self.bin_list.len()).collect();
+		util::par_map_list(&bin_index_list, |&bin_index| self.bin_as_mip_chain(bin_index, levels))
+	}
+
+	/// Generates the mip chain of the indicated bin, as described by `as_mip_chains`.
+	fn bin_as_mip_chain(&self, bin_index: usize, levels: u32) -> Vec<DynamicImage>
+	{
+		let image = util::image_from_bin(&self.rect_list, &self.bin_list[bin_index]);
+		util::mip_chain(&image, &self.bin_content_list(bin_index), self.padding, levels)
+	}
+
+	/// Generates a single image with every bin stacked vertically, one layer after another.
+	///
+	/// Each layer is as large as the largest bin, which is every bin in array mode.
+	///
+	pub fn as_image_array(&self) -> DynamicImage
+	{
+		util::image_stack(&self.as_images())
+	}
+
+	/// Generates a KTX2 texture array container with one layer for each bin.
+	pub fn as_ktx2(&self) -> Vec<u8>
+	{
+		let image_list = self.as_images();
+		let layer_count = image_list.len() as u32;
+		let image = util::image_stack(&image_list).to_rgba();
+		let (width, height) = image.dimensions();
+		util::ktx2_from_layers(width, height / max(layer_count, 1), layer_count, &image.into_raw())
 	}
 }
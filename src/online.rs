@@ -0,0 +1,463 @@
+//! Online packing of rects that arrive one at a time.
+
+use std::cmp::{max, min};
+
+use {AtlasBin, AtlasPart};
+use util::{Rect, Region};
+
+/// The result of inserting a rect into an `OnlinePacker`.
+#[derive(Copy, Clone, Debug)]
+pub enum Placement
+{
+	/// The rect was added as the given part to the bin with the given index.
+	Placed(usize, AtlasPart),
+
+	/// The rect is empty, does not fit in an empty bin or needs a bin past the bin limit.
+	Unplaced,
+}
+
+impl Placement
+{
+	/// Returns the bin index and part of a placed rect.
+	pub fn part(&self) -> Option<(usize, AtlasPart)>
+	{
+		match *self
+		{
+			Placement::Placed(bin_index, part) => Some((bin_index, part)),
+			Placement::Unplaced => None,
+		}
+	}
+
+	/// Returns true if the rect was placed.
+	pub fn is_placed(&self) -> bool
+	{
+		self.part().is_some()
+	}
+}
+
+/// A packer that places each rect as soon as it is inserted.
+///
+/// Unlike `AtlasGenerator`, which needs the complete list of rects up front, an online packer never
+/// moves rects that were already placed. Results are usually worse than generating all at once,
+/// but every placement is known immediately, for instance when sprites are added interactively.
+///
+/// Parts reference rects by the order they were inserted in, counting rects that were not placed.
+///
+pub trait OnlinePacker
+{
+	/// Places the given rect, adding a bin if it does not fit in any existing bin.
+	fn insert(&mut self, rect: Rect) -> Placement;
+
+	/// Returns the bins containing the placed rects.
+	fn bin_list(&self) -> &[AtlasBin];
+}
+
+/// The free space of a single bin, as tracked by a packing strategy.
+trait Strategy
+{
+	/// Creates the free space of an empty bin.
+	fn new(dimensions: Rect) -> Self;
+
+	/// Returns the position and orientation to place a rect at, using the first orientation of the
+	/// given orientations that fits best.
+	fn find(&self, orientation_list: &[(bool, Rect)]) -> Option<(u32, u32, bool)>;
+
+	/// Marks the given region as occupied.
+	fn occupy(&mut self, region: Region);
+}
+
+/// The bins of a packer along with the free space of each bin.
+struct Packer<S: Strategy>
+{
+	dimensions: Rect,
+	rotate: bool,
+	max_bins: Option<usize>,
+	bin_list: Vec<AtlasBin>,
+	space_list: Vec<S>,
+	rect_count: usize,
+}
+
+impl<S: Strategy> Packer<S>
+{
+	fn new(width: u32, height: u32) -> Self
+	{
+		Packer
+		{
+			dimensions: Rect::new(width, height),
+			rotate: false,
+			max_bins: None,
+			bin_list: Vec::new(),
+			space_list: Vec::new(),
+			rect_count: 0,
+		}
+	}
+
+	fn insert(&mut self, rect: Rect) -> Placement
+	{
+		let rect_index = self.rect_count;
+		self.rect_count += 1;
+		if rect.width == 0 || rect.height == 0
+		{
+			return Placement::Unplaced;
+		}
+
+		let mut orientation_list = vec![(false, rect)];
+		if self.rotate && rect.width != rect.height
+		{
+			orientation_list.push((true, Rect::new(rect.height, rect.width)));
+		}
+		let dimensions = self.dimensions;
+		orientation_list.retain(|&(_, size)| size.width <= dimensions.width && size.height <= dimensions.height);
+		if orientation_list.is_empty()
+		{
+			return Placement::Unplaced;
+		}
+
+		let mut found = self.space_list.iter()
+			.enumerate()
+			.filter_map(|(bin_index, space)| space.find(&orientation_list).map(|position| (bin_index, position)))
+			.next();
+		if found.is_none() && self.max_bins.is_none_or(|max_bins| self.bin_list.len() < max_bins)
+		{
+			let space = S::new(self.dimensions);
+			found = space.find(&orientation_list).map(|position| (self.bin_list.len(), position));
+			self.space_list.push(space);
+			self.bin_list.push(AtlasBin::new(Rect::new(0, 0), Vec::new()));
+		}
+
+		match found
+		{
+			Some((bin_index, (x, y, rotate))) =>
+			{
+				let size = if rotate { Rect::new(rect.height, rect.width) } else { rect };
+				self.space_list[bin_index].occupy(Region::new(x, y, size.width, size.height));
+				self.bin_list[bin_index].part_add(rect_index, x, y, size, rotate);
+				Placement::Placed(bin_index, *self.bin_list[bin_index].part_list().last().unwrap())
+			}
+			None => Placement::Unplaced,
+		}
+	}
+}
+
+/// Returns the orientation and position with the lowest key from the given candidates, keeping the
+/// first candidate on ties.
+fn candidate_best<K: Ord, I: Iterator<Item = (K, (u32, u32, bool))>>(candidate_iter: I) -> Option<(u32, u32, bool)>
+{
+	let mut best: Option<(K, (u32, u32, bool))> = None;
+	for (key, position) in candidate_iter
+	{
+		if best.as_ref().is_none_or(|(best_key, _)| key < *best_key)
+		{
+			best = Some((key, position));
+		}
+	}
+	best.map(|(_, position)| position)
+}
+
+/// Adds the builder methods shared by every packer.
+macro_rules! packer_builder
+{
+	($packer:ident) =>
+	{
+		impl $packer
+		{
+			/// Creates a packer without any bins where each bin has at most the given dimensions.
+			pub fn new(width: u32, height: u32) -> Self
+			{
+				$packer(Packer::new(width, height))
+			}
+
+			/// Allows rects to be rotated 90 degrees clockwise when that fits better.
+			pub fn rotate(mut self, rotate: bool) -> Self
+			{
+				self.0.rotate = rotate;
+				self
+			}
+
+			/// Limits the number of bins that the packer may create.
+			pub fn max_bins(mut self, max_bins: usize) -> Self
+			{
+				self.0.max_bins = Some(max_bins);
+				self
+			}
+		}
+
+		impl OnlinePacker for $packer
+		{
+			fn insert(&mut self, rect: Rect) -> Placement
+			{
+				self.0.insert(rect)
+			}
+
+			fn bin_list(&self) -> &[AtlasBin]
+			{
+				&self.0.bin_list
+			}
+		}
+	}
+}
+
+/// A horizontal segment of a skyline.
+#[derive(Copy, Clone, Debug)]
+struct Segment
+{
+	x: u32,
+	y: u32,
+	width: u32,
+}
+
+/// The top edge of the occupied space of a bin, as a list of segments from left to right.
+struct Skyline
+{
+	dimensions: Rect,
+	segment_list: Vec<Segment>,
+}
+
+impl Strategy for Skyline
+{
+	fn new(dimensions: Rect) -> Self
+	{
+		Skyline
+		{
+			dimensions,
+			segment_list: vec![Segment { x: 0, y: 0, width: dimensions.width }],
+		}
+	}
+
+	fn find(&self, orientation_list: &[(bool, Rect)]) -> Option<(u32, u32, bool)>
+	{
+		// Rects rest on the highest segment below them, placing them as low as possible.
+		let candidate_iter = orientation_list.iter().flat_map(|&(rotate, size)|
+		{
+			(0..self.segment_list.len()).filter_map(move |segment_index|
+			{
+				let x = self.segment_list[segment_index].x;
+				if x + size.width > self.dimensions.width
+				{
+					return None;
+				}
+				let y = self.segment_list[segment_index..].iter()
+					.take_while(|segment| segment.x < x + size.width)
+					.map(|segment| segment.y)
+					.max()
+					.unwrap();
+				if y + size.height > self.dimensions.height
+				{
+					return None;
+				}
+				Some(((y + size.height, x), (x, y, rotate)))
+			})
+		});
+		candidate_best(candidate_iter)
+	}
+
+	fn occupy(&mut self, region: Region)
+	{
+		let segment_index = self.segment_list.iter().position(|segment| segment.x == region.x).unwrap();
+		self.segment_list.insert(segment_index, Segment { x: region.x, y: region.bottom(), width: region.width });
+
+		// Segments below the rect are covered by it.
+		let next_index = segment_index + 1;
+		while next_index < self.segment_list.len() && self.segment_list[next_index].x < region.right()
+		{
+			let segment = self.segment_list[next_index];
+			if segment.x + segment.width <= region.right()
+			{
+				self.segment_list.remove(next_index);
+			}
+			else
+			{
+				self.segment_list[next_index] = Segment
+				{
+					x: region.right(),
+					y: segment.y,
+					width: segment.x + segment.width - region.right(),
+				};
+				break;
+			}
+		}
+
+		// Neighbouring segments of equal height are merged.
+		let mut segment_index = 0;
+		while segment_index + 1 < self.segment_list.len()
+		{
+			let next = self.segment_list[segment_index + 1];
+			if self.segment_list[segment_index].y == next.y
+			{
+				self.segment_list[segment_index].width += next.width;
+				self.segment_list.remove(segment_index + 1);
+			}
+			else
+			{
+				segment_index += 1;
+			}
+		}
+	}
+}
+
+/// A packer that keeps the top edge of the occupied space as a skyline.
+///
+/// Each rect is placed as low as possible on the skyline. This packer is fast and works well for
+/// rects of similar heights, but space below overhanging rects is lost.
+///
+pub struct SkylinePacker(Packer<Skyline>);
+
+packer_builder!(SkylinePacker);
+
+/// A row of rects of at most the height of the first rect in it.
+#[derive(Copy, Clone, Debug)]
+struct Shelf
+{
+	y: u32,
+	height: u32,
+	width_used: u32,
+}
+
+/// The shelves of a bin from top to bottom.
+struct Shelves
+{
+	dimensions: Rect,
+	shelf_list: Vec<Shelf>,
+}
+
+impl Strategy for Shelves
+{
+	fn new(dimensions: Rect) -> Self
+	{
+		Shelves
+		{
+			dimensions,
+			shelf_list: Vec::new(),
+		}
+	}
+
+	fn find(&self, orientation_list: &[(bool, Rect)]) -> Option<(u32, u32, bool)>
+	{
+		// The first shelf that fits is used, with the orientation wasting the least height.
+		for shelf in &self.shelf_list
+		{
+			let candidate_iter = orientation_list.iter()
+				.filter(|&&(_, size)| size.height <= shelf.height && shelf.width_used + size.width <= self.dimensions.width)
+				.map(|&(rotate, size)| (shelf.height - size.height, (shelf.width_used, shelf.y, rotate)));
+			if let Some(position) = candidate_best(candidate_iter)
+			{
+				return Some(position);
+			}
+		}
+
+		// Otherwise a new shelf is opened with the orientation lying the flattest.
+		let y = self.shelf_list.last().map_or(0, |shelf| shelf.y + shelf.height);
+		let candidate_iter = orientation_list.iter()
+			.filter(|&&(_, size)| y + size.height <= self.dimensions.height)
+			.map(|&(rotate, size)| (size.height, (0, y, rotate)));
+		candidate_best(candidate_iter)
+	}
+
+	fn occupy(&mut self, region: Region)
+	{
+		match self.shelf_list.iter_mut().find(|shelf| shelf.y == region.y)
+		{
+			Some(shelf) => shelf.width_used = region.right(),
+			None => self.shelf_list.push(Shelf { y: region.y, height: region.height, width_used: region.right() }),
+		}
+	}
+}
+
+/// A packer that places rects left to right in rows called shelves.
+///
+/// Each shelf is as tall as the first rect placed in it. This is the simplest and fastest packer,
+/// and works well when rects have similar heights, such as glyphs of a single font.
+///
+pub struct ShelfPacker(Packer<Shelves>);
+
+packer_builder!(ShelfPacker);
+
+/// The maximal free regions of a bin, which may overlap each other.
+struct MaxRects
+{
+	free_list: Vec<Region>,
+}
+
+impl Strategy for MaxRects
+{
+	fn new(dimensions: Rect) -> Self
+	{
+		MaxRects
+		{
+			free_list: vec![Region::new(0, 0, dimensions.width, dimensions.height)],
+		}
+	}
+
+	fn find(&self, orientation_list: &[(bool, Rect)]) -> Option<(u32, u32, bool)>
+	{
+		// The free region leaving the least space along its shorter side is used.
+		let candidate_iter = orientation_list.iter().flat_map(|&(rotate, size)|
+		{
+			self.free_list.iter()
+				.filter(move |free| size.width <= free.width && size.height <= free.height)
+				.map(move |free|
+				{
+					let leftover_width = free.width - size.width;
+					let leftover_height = free.height - size.height;
+					let key = (min(leftover_width, leftover_height), max(leftover_width, leftover_height), free.y, free.x);
+					(key, (free.x, free.y, rotate))
+				})
+		});
+		candidate_best(candidate_iter)
+	}
+
+	fn occupy(&mut self, region: Region)
+	{
+		// Every free region overlapping the rect is replaced by the maximal regions around it.
+		let mut free_list = Vec::with_capacity(self.free_list.len() + 4);
+		for free in &self.free_list
+		{
+			if !free.intersects(&region)
+			{
+				free_list.push(*free);
+				continue;
+			}
+			let piece_list =
+			[
+				Region::new(free.x, free.y, region.x.saturating_sub(free.x), free.height),
+				Region::new(region.right(), free.y, free.right().saturating_sub(region.right()), free.height),
+				Region::new(free.x, free.y, free.width, region.y.saturating_sub(free.y)),
+				Region::new(free.x, region.bottom(), free.width, free.bottom().saturating_sub(region.bottom())),
+			];
+			free_list.extend(piece_list.iter().filter(|piece| piece.width != 0 && piece.height != 0));
+		}
+
+		// Regions inside of other regions are redundant.
+		let contains = |outer: &Region, inner: &Region|
+		{
+			outer.x <= inner.x && outer.y <= inner.y && inner.right() <= outer.right() && inner.bottom() <= outer.bottom()
+		};
+		let mut free_index = 0;
+		while free_index < free_list.len()
+		{
+			let free = free_list[free_index];
+			let redundant = free_list.iter().enumerate().any(|(other_index, other)|
+			{
+				other_index != free_index && contains(other, &free) && (!contains(&free, other) || other_index < free_index)
+			});
+			if redundant
+			{
+				free_list.remove(free_index);
+			}
+			else
+			{
+				free_index += 1;
+			}
+		}
+		self.free_list = free_list;
+	}
+}
+
+/// A packer that tracks every maximal free region of each bin.
+///
+/// Rects are placed in the free region that fits them the tightest. This packer is the slowest but
+/// wastes the least space, especially for rects of varying sizes.
+///
+pub struct MaxRectsPacker(Packer<MaxRects>);
+
+packer_builder!(MaxRectsPacker);
@@ -0,0 +1,162 @@
+use std::cmp::max;
+
+use util::Rect;
+
+/// The maximal number of sizes tried for each dimension by the Martello and Vigo bounds.
+const CANDIDATE_LIMIT: usize = 32;
+
+/// Returns the quotient of the given numbers, rounded up.
+pub(crate) fn div_ceil(value: u64, divisor: u64) -> u64
+{
+	value.div_ceil(divisor)
+}
+
+/// Returns the distinct values from 1 up to the half, always including the half.
+///
+/// Long lists are thinned out evenly. The bounds stay valid for any subset of values, since each
+/// value gives a bound of its own.
+///
+fn candidate_list<I: Iterator<Item = u64>>(value_iter: I, half: u64) -> Vec<u64>
+{
+	if half == 0
+	{
+		return Vec::new();
+	}
+	let mut value_list: Vec<u64> = value_iter.filter(|&value| value >= 1 && value <= half).collect();
+	value_list.push(half);
+	value_list.sort();
+	value_list.dedup();
+	if value_list.len() > CANDIDATE_LIMIT
+	{
+		let last = value_list.len() - 1;
+		value_list = (0..CANDIDATE_LIMIT).map(|index| value_list[index * last / (CANDIDATE_LIMIT - 1)]).collect();
+	}
+	value_list
+}
+
+/// Returns the number of bins needed to fit the total area of the given rects.
+pub fn lower_bound_area(rect_list: &[Rect], size: Rect) -> usize
+{
+	let total_area: u64 = rect_list.iter().map(|rect| rect.width as u64 * rect.height as u64).sum();
+	div_ceil(total_area, size.width as u64 * size.height as u64) as usize
+}
+
+/// Returns the L1 bound of Martello and Vigo along the width.
+///
+/// Rects taller than half of the bin can never be stacked, so they form a one dimensional problem
+/// over their widths.
+///
+fn lower_bound_l1(dimension_list: &[(u64, u64)], width: u64, height: u64) -> usize
+{
+	let width_list: Vec<u64> = dimension_list.iter()
+		.filter(|&&(_, rect_height)| rect_height * 2 > height)
+		.map(|&(rect_width, _)| rect_width)
+		.collect();
+	let mut bound = width_list.iter().filter(|&&rect_width| rect_width * 2 > width).count();
+	for p in candidate_list(width_list.iter().cloned(), width / 2)
+	{
+		// Wide rects each need their own bin and narrow rects only fit next to the medium ones.
+		let mut wide_count = 0;
+		let mut medium_count = 0;
+		let mut medium_sum = 0;
+		let mut narrow_sum = 0;
+		for &rect_width in &width_list
+		{
+			if rect_width > width - p
+			{
+				wide_count += 1;
+			}
+			else if rect_width * 2 > width
+			{
+				medium_count += 1;
+				medium_sum += rect_width;
+			}
+			else if rect_width >= p
+			{
+				narrow_sum += rect_width;
+			}
+		}
+		let free = medium_count * width - medium_sum;
+		let extra = div_ceil(narrow_sum.saturating_sub(free), width);
+		bound = max(bound, (wide_count + medium_count + extra) as usize);
+	}
+	bound
+}
+
+/// Returns the L2 bound of Martello and Vigo.
+///
+/// Rects larger than half of the bin in both dimensions each need their own bin. The largest of
+/// them leave too little room for rects of at least the candidate size, which must then fit in the
+/// space left by the others or in new bins.
+///
+fn lower_bound_l2(dimension_list: &[(u64, u64)], width: u64, height: u64) -> usize
+{
+	let bin_area = width * height;
+	let large_list: Vec<(u64, u64)> = dimension_list.iter()
+		.filter(|&&(rect_width, rect_height)| rect_width * 2 > width && rect_height * 2 > height)
+		.cloned()
+		.collect();
+	let mut bound = large_list.len();
+	let p_list = candidate_list(dimension_list.iter().map(|&(rect_width, _)| rect_width), width / 2);
+	let q_list = candidate_list(dimension_list.iter().map(|&(_, rect_height)| rect_height), height / 2);
+	for &p in &p_list
+	{
+		for &q in &q_list
+		{
+			let mut free = 0;
+			for &(rect_width, rect_height) in &large_list
+			{
+				if rect_width <= width - p || rect_height <= height - q
+				{
+					free += bin_area.saturating_sub(rect_width * rect_height);
+				}
+			}
+			let small_area: u64 = dimension_list.iter()
+				.filter(|&&(rect_width, rect_height)|
+				{
+					rect_width >= p && rect_width * 2 <= width && rect_height >= q && rect_height * 2 <= height
+				})
+				.map(|&(rect_width, rect_height)| rect_width * rect_height)
+				.sum();
+			let extra = div_ceil(small_area.saturating_sub(free), bin_area);
+			bound = max(bound, large_list.len() + extra as usize);
+		}
+	}
+	bound
+}
+
+/// Returns a lower bound of the number of bins of the given size needed to pack the given rects.
+///
+/// The bound is the largest of the area bound and the L1 and L2 bounds of Martello and Vigo. When
+/// rotations are allowed, the Martello and Vigo bounds do not apply, so only rects that are larger
+/// than half of the bin in every orientation are counted on top of the area bound.
+///
+pub fn lower_bound(rect_list: &[Rect], size: Rect, rotate: bool) -> usize
+{
+	assert!(size.width != 0 && size.height != 0);
+	let width = size.width as u64;
+	let height = size.height as u64;
+	let dimension_list: Vec<(u64, u64)> = rect_list.iter()
+		.map(|rect| (rect.width as u64, rect.height as u64))
+		.collect();
+	let bound = lower_bound_area(rect_list, size);
+	if rotate
+	{
+		let is_large = |rect_width: u64, rect_height: u64|
+		{
+			rect_width > width || rect_height > height || (rect_width * 2 > width && rect_height * 2 > height)
+		};
+		let large_count = dimension_list.iter()
+			.filter(|&&(rect_width, rect_height)| is_large(rect_width, rect_height) && is_large(rect_height, rect_width))
+			.count();
+		max(bound, large_count)
+	}
+	else
+	{
+		let transposed_list: Vec<(u64, u64)> = dimension_list.iter()
+			.map(|&(rect_width, rect_height)| (rect_height, rect_width))
+			.collect();
+		let l1 = max(lower_bound_l1(&dimension_list, width, height), lower_bound_l1(&transposed_list, height, width));
+		max(max(bound, l1), lower_bound_l2(&dimension_list, width, height))
+	}
+}
@@ -1,8 +1,11 @@
 use std::borrow::Borrow;
+use std::cmp::max;
+use std::collections::VecDeque;
 
-use image::{DynamicImage, GenericImage, Pixel, Rgb, Rgba};
+use image::{DynamicImage, GenericImage, ImageBuffer, Pixel, Rgb, Rgba, RgbaImage};
 
 use ::{AtlasBin, AtlasRect};
+use util::{Region, MAX_MIP_LEVELS};
 
 impl AtlasRect for DynamicImage
 {
@@ -101,39 +104,208 @@ pub fn border_crop(image: &mut DynamicImage) -> (DynamicImage, u32, u32, u32, u3
 	(image.crop(left, top, right - left, bottom - top), left, right, top, bottom)
 }
 
-pub(crate) fn image_from_bin<T>(rect_list: &[T], bin: &AtlasBin) -> DynamicImage
+/// Number of pixels in each dimension of the tiles used when transposing.
+const TRANSPOSE_TILE: usize = 32;
+
+/// Calls the given function with the image as an RGBA buffer, converting only if necessary.
+fn with_rgba<R, F>(image: &DynamicImage, function: F) -> R where F: FnOnce(&RgbaImage) -> R
+{
+	match *image
+	{
+		DynamicImage::ImageRgba8(ref buffer) => function(buffer),
+		ref image => function(&image.to_rgba()),
+	}
+}
+
+/// Copies the given RGBA buffer into the target buffer at the given position.
+///
+/// If `rotate` is true, the source is rotated 90 degrees clockwise. Rotation is done in square
+/// tiles so that both the rows being read and the rows being written stay in the cache.
+///
+fn copy_rgba(target: &mut RgbaImage, source: &RgbaImage, x: usize, y: usize, rotate: bool)
+{
+	let stride = target.width() as usize * 4;
+	let (width, height) = (source.width() as usize, source.height() as usize);
+	let source: &[u8] = source;
+	let target: &mut [u8] = target;
+
+	if !rotate
+	{
+		for row in 0..height
+		{
+			let offset = (y + row) * stride + x * 4;
+			target[offset..offset + width * 4].copy_from_slice(&source[row * width * 4..(row + 1) * width * 4]);
+		}
+		return;
+	}
+
+	for row_tile in (0..height).step_by(TRANSPOSE_TILE)
+	{
+		let row_end = ::std::cmp::min(row_tile + TRANSPOSE_TILE, height);
+		for column_tile in (0..width).step_by(TRANSPOSE_TILE)
+		{
+			let column_end = ::std::cmp::min(column_tile + TRANSPOSE_TILE, width);
+			for column in column_tile..column_end
+			{
+				// Source column becomes a target row, written from right to left.
+				let offset = (y + column) * stride + (x + height - 1) * 4;
+				for row in row_tile..row_end
+				{
+					let source_offset = (row * width + column) * 4;
+					let target_offset = offset - row * 4;
+					target[target_offset..target_offset + 4].copy_from_slice(&source[source_offset..source_offset + 4]);
+				}
+			}
+		}
+	}
+}
+
+pub(crate) fn rgba_from_bin<T>(rect_list: &[T], bin: &AtlasBin) -> RgbaImage
 	where T: AtlasRect + Borrow<DynamicImage>
 {
-	let dimensions = (bin as &AtlasRect).dimensions();
-	let mut image = DynamicImage::new_rgba8(dimensions.width, dimensions.height);
+	let dimensions = (bin as &dyn AtlasRect).dimensions();
+	let mut image = RgbaImage::new(dimensions.width, dimensions.height);
 
 	for reference in &bin.part_list
 	{
-		let texture = &rect_list[reference.rect_index];
-		if !reference.rotate
+		with_rgba(rect_list[reference.rect_index].borrow(), |texture|
+		{
+			copy_rgba(&mut image, texture, reference.x as usize, reference.y as usize, reference.rotate);
+		});
+	}
+	image
+}
+
+pub(crate) fn image_from_bin<T>(rect_list: &[T], bin: &AtlasBin) -> DynamicImage
+	where T: AtlasRect + Borrow<DynamicImage>
+{
+	DynamicImage::ImageRgba8(rgba_from_bin(rect_list, bin))
+}
+
+/// Stacks the given images vertically into layers as large as the largest image.
+pub fn image_stack(image_list: &[DynamicImage]) -> DynamicImage
+{
+	let width = image_list.iter().map(GenericImage::width).max().unwrap_or(0);
+	let height = image_list.iter().map(GenericImage::height).max().unwrap_or(0);
+	let mut stacked = RgbaImage::new(width, height * image_list.len() as u32);
+
+	for (layer, image) in image_list.iter().enumerate()
+	{
+		with_rgba(image, |buffer| copy_rgba(&mut stacked, buffer, 0, layer * height as usize, false));
+	}
+	DynamicImage::ImageRgba8(stacked)
+}
+
+/// Fills pixels outside of the masked pixels with the color of the nearest masked pixel.
+///
+/// Only pixels within the given distance of a masked pixel are filled.
+///
+fn dilate(data: &mut [u8], width: u32, height: u32, mut mask: Vec<bool>, distance: u32)
+{
+	let mut queue = VecDeque::new();
+	for (index, &masked) in mask.iter().enumerate()
+	{
+		if masked
+		{
+			queue.push_back((index, 0));
+		}
+	}
+
+	let (width, height) = (width as usize, height as usize);
+	while let Some((index, step)) = queue.pop_front()
+	{
+		if step == distance
 		{
-			for x in 0..AtlasRect::width(texture) as u32
+			continue;
+		}
+		let (x, y) = (index % width, index / width);
+		let neighbour_list =
+		[
+			if x > 0 { Some(index - 1) } else { None },
+			if x + 1 < width { Some(index + 1) } else { None },
+			if y > 0 { Some(index - width) } else { None },
+			if y + 1 < height { Some(index + width) } else { None },
+		];
+		for &neighbour in neighbour_list.iter().filter_map(|neighbour| neighbour.as_ref())
+		{
+			if !mask[neighbour]
 			{
-				for y in 0..AtlasRect::height(texture) as u32
+				mask[neighbour] = true;
+				for channel in 0..4
 				{
-					let pixel = texture.borrow().get_pixel(x, y);
-					image.put_pixel(reference.x + x, reference.y + y, pixel);
+					data[neighbour * 4 + channel] = data[index * 4 + channel];
 				}
+				queue.push_back((neighbour, step + 1));
 			}
 		}
-		else
+	}
+}
+
+/// Halves the given image by averaging every 2x2 block of pixels.
+fn downsample(data: &[u8], width: u32, height: u32) -> (Vec<u8>, u32, u32)
+{
+	let (width_half, height_half) = (max(width / 2, 1), max(height / 2, 1));
+	let mut result = Vec::with_capacity(width_half as usize * height_half as usize * 4);
+	for y in 0..height_half
+	{
+		for x in 0..width_half
 		{
-			for x in 0..AtlasRect::width(texture) as u32
+			for channel in 0..4
 			{
-				for y in 0..AtlasRect::height(texture) as u32
+				let mut sum = 0u32;
+				for &(sample_x, sample_y) in &[(0, 0), (1, 0), (0, 1), (1, 1)]
 				{
-					let pixel = texture.borrow().get_pixel(x, y);
-					image.put_pixel(reference.x + (AtlasRect::height(texture) - 1 - y), reference.y + x, pixel);
+					let sample_x = ::std::cmp::min(x * 2 + sample_x, width - 1);
+					let sample_y = ::std::cmp::min(y * 2 + sample_y, height - 1);
+					sum += data[((sample_y * width + sample_x) * 4) as usize + channel] as u32;
 				}
+				result.push(((sum + 2) / 4) as u8);
 			}
 		}
 	}
-	image
+	(result, width_half, height_half)
+}
+
+/// Generates the given number of mip levels for an image, starting with a dilated copy of itself.
+///
+/// The regions are the content of each rect in the image. Before every level is stored, the edges
+/// of the regions scaled to that level are dilated by the padding scaled to that level, but always
+/// by at least a single pixel. At most `MAX_MIP_LEVELS` levels can be generated.
+///
+pub fn mip_chain(image: &DynamicImage, region_list: &[Region], padding: u32, levels: u32) -> Vec<DynamicImage>
+{
+	assert!(levels <= MAX_MIP_LEVELS);
+	let mut chain = Vec::with_capacity(levels as usize);
+	let (mut width, mut height) = image.dimensions();
+	let mut data = image.to_rgba().into_raw();
+
+	for level in 0..levels
+	{
+		let mut mask = vec![false; width as usize * height as usize];
+		for region in region_list
+		{
+			let scale = 1 << level;
+			let right = ::std::cmp::min((region.right() + scale - 1) >> level, width);
+			let bottom = ::std::cmp::min((region.bottom() + scale - 1) >> level, height);
+			for y in (region.y >> level)..bottom
+			{
+				for x in (region.x >> level)..right
+				{
+					mask[(y * width + x) as usize] = true;
+				}
+			}
+		}
+		dilate(&mut data, width, height, mask, max(padding >> level, 1));
+
+		let buffer = ImageBuffer::<Rgba<u8>, Vec<u8>>::from_raw(width, height, data.clone()).unwrap();
+		chain.push(DynamicImage::ImageRgba8(buffer));
+
+		let (next, next_width, next_height) = downsample(&data, width, height);
+		data = next;
+		width = next_width;
+		height = next_height;
+	}
+	chain
 }
 
 #[derive(Debug)]
@@ -187,7 +359,8 @@ pub(crate) fn colors_from_bin<T>(color_weight: f32, rect_list: &[T], bin: &Atlas
 {
 	let mut color_current = Hsv { data: [0, 255, 255] };
 
-	let mut image = DynamicImage::new_rgba8(bin.dimensions.width as u32, bin.dimensions.height as u32);
+	let stride = bin.dimensions.width as usize * 4;
+	let mut data = vec![0; stride * bin.dimensions.height as usize];
 
 	for reference in &bin.part_list
 	{
@@ -196,13 +369,14 @@ pub(crate) fn colors_from_bin<T>(color_weight: f32, rect_list: &[T], bin: &Atlas
 		let rotate = reference.rotate;
 		let dimensions = (&rect_list[reference.rect_index] as &AtlasRect).dimensions_rotated(rotate);
 
-		for x in reference.x..(reference.x + dimensions.width)
+		// Fill a single row, then copy it to every other row.
+		let pixel = color_current.to_rgb().to_rgba().data;
+		let row: Vec<u8> = pixel.iter().cycle().take(dimensions.width as usize * 4).cloned().collect();
+		for y in reference.y..(reference.y + dimensions.height)
 		{
-			for y in reference.y..(reference.y + dimensions.height)
-			{
-				image.put_pixel(x as u32, y as u32, color_current.to_rgb().to_rgba());
-			}
+			let target = y as usize * stride + reference.x as usize * 4;
+			data[target..target + row.len()].copy_from_slice(&row);
 		}
 	}
-	image
+	DynamicImage::ImageRgba8(ImageBuffer::from_raw(bin.dimensions.width, bin.dimensions.height, data).unwrap())
 }
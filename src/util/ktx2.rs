@@ -0,0 +1,78 @@
+const KTX2_IDENTIFIER: [u8; 12] = [0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A];
+
+/// `VK_FORMAT_R8G8B8A8_UNORM`.
+const KTX2_FORMAT_RGBA8: u32 = 37;
+
+/// Size of the header and its index, followed by a single level index entry.
+const KTX2_HEADER_SIZE: u32 = 80 + 24;
+
+/// Size of the data format descriptor, including its total size prefix.
+const KTX2_DFD_SIZE: u32 = 4 + 24 + 16 * 4;
+
+fn push_u32(data: &mut Vec<u8>, value: u32)
+{
+	data.extend_from_slice(&[value as u8, (value >> 8) as u8, (value >> 16) as u8, (value >> 24) as u8]);
+}
+
+fn push_u64(data: &mut Vec<u8>, value: u64)
+{
+	push_u32(data, value as u32);
+	push_u32(data, (value >> 32) as u32);
+}
+
+/// Writes a KTX2 container holding an uncompressed RGBA8 texture array.
+///
+/// The pixel data must contain every layer one after another, each being `width * height` pixels
+/// of 4 bytes. A single layer is written as an array with one layer.
+///
+pub fn ktx2_from_layers(width: u32, height: u32, layer_count: u32, rgba: &[u8]) -> Vec<u8>
+{
+	let level_size = width as u64 * height as u64 * layer_count as u64 * 4;
+	assert_eq!(rgba.len() as u64, level_size);
+
+	let mut data = Vec::with_capacity((KTX2_HEADER_SIZE + KTX2_DFD_SIZE) as usize + rgba.len());
+	data.extend_from_slice(&KTX2_IDENTIFIER);
+
+	// Header.
+	push_u32(&mut data, KTX2_FORMAT_RGBA8);
+	push_u32(&mut data, 1);
+	push_u32(&mut data, width);
+	push_u32(&mut data, height);
+	push_u32(&mut data, 0);
+	push_u32(&mut data, layer_count);
+	push_u32(&mut data, 1);
+	push_u32(&mut data, 1);
+	push_u32(&mut data, 0);
+
+	// Index. There is no key/value or supercompression data.
+	push_u32(&mut data, KTX2_HEADER_SIZE);
+	push_u32(&mut data, KTX2_DFD_SIZE);
+	push_u32(&mut data, 0);
+	push_u32(&mut data, 0);
+	push_u64(&mut data, 0);
+	push_u64(&mut data, 0);
+
+	// Level index.
+	push_u64(&mut data, (KTX2_HEADER_SIZE + KTX2_DFD_SIZE) as u64);
+	push_u64(&mut data, level_size);
+	push_u64(&mut data, level_size);
+
+	// Basic data format descriptor for linear RGBA with straight alpha.
+	push_u32(&mut data, KTX2_DFD_SIZE);
+	push_u32(&mut data, 0);
+	push_u32(&mut data, 2 | ((KTX2_DFD_SIZE - 4) << 16));
+	push_u32(&mut data, 1 | (1 << 8) | (1 << 16));
+	push_u32(&mut data, 0);
+	push_u32(&mut data, 4);
+	push_u32(&mut data, 0);
+	for (channel, &channel_type) in [0u32, 1, 2, 15].iter().enumerate()
+	{
+		push_u32(&mut data, (channel as u32 * 8) | (7 << 16) | (channel_type << 24));
+		push_u32(&mut data, 0);
+		push_u32(&mut data, 0);
+		push_u32(&mut data, 255);
+	}
+
+	data.extend_from_slice(rgba);
+	data
+}
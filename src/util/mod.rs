@@ -2,14 +2,34 @@
 
 #[cfg(feature = "image")]
 mod img;
+mod bound;
+mod ktx2;
+mod rng;
 
 #[cfg(feature = "image")]
 pub use self::img::*;
+pub use self::bound::{lower_bound, lower_bound_area};
+pub(crate) use self::bound::div_ceil;
+pub use self::ktx2::*;
+pub use self::rng::Rng;
 
 use AtlasRect;
 
+/// The largest number of mip levels supported, which is enough for bins of up to 32768 pixels.
+pub const MAX_MIP_LEVELS: u32 = 16;
+
+/// Maps every item of the given list in parallel.
+#[cfg(feature = "rayon")]
+pub(crate) fn par_map_list<I, R, F>(list: &[I], function: F) -> Vec<R>
+	where I: Sync, R: Send, F: Fn(&I) -> R + Sync + Send
+{
+	use rayon::prelude::*;
+	list.par_iter().map(function).collect()
+}
+
 /// An axis-aligned rectangle.
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Rect
 {
 	/// The width dimension of the rectangle.
@@ -57,6 +77,7 @@ impl<T: AsRef<Rect>> AtlasRect for T
 
 /// An axis-aligned rectangle that can be rotated.
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct RotatableRect
 {
 	/// The dimensions of the rectangle.
@@ -85,3 +106,154 @@ impl AsRef<Rect> for RotatableRect
 		&self.rect
 	}
 }
+
+/// An axis-aligned rectangle positioned inside of a bin.
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Region
+{
+	/// The x-position of the left edge of the region.
+	pub x: u32,
+
+	/// The y-position of the top edge of the region.
+	pub y: u32,
+
+	/// The width dimension of the region.
+	pub width: u32,
+
+	/// The height dimension of the region.
+	pub height: u32,
+}
+
+impl Region
+{
+	pub fn new(x: u32, y: u32, width: u32, height: u32) -> Self
+	{
+		Self
+		{
+			x,
+			y,
+			width,
+			height,
+		}
+	}
+
+	/// Returns the x-position just past the right edge of the region.
+	pub fn right(&self) -> u32
+	{
+		self.x + self.width
+	}
+
+	/// Returns the y-position just past the bottom edge of the region.
+	pub fn bottom(&self) -> u32
+	{
+		self.y + self.height
+	}
+
+	/// Returns the dimensions of the region.
+	pub fn dimensions(&self) -> Rect
+	{
+		Rect::new(self.width, self.height)
+	}
+
+	/// Returns true if this region and the given region share any area.
+	pub fn intersects(&self, other: &Region) -> bool
+	{
+		self.x < other.right() && other.x < self.right() && self.y < other.bottom() && other.y < self.bottom()
+	}
+}
+
+impl AtlasRect for Region
+{
+	fn width(&self) -> u32
+	{
+		self.width
+	}
+	fn height(&self) -> u32
+	{
+		self.height
+	}
+}
+
+/// Returns the largest region of the given dimensions that does not intersect any occupied region.
+///
+/// Occupied regions may extend past the dimensions. An empty region is returned if there is no
+/// free space. Among equally large regions, the top-most and then left-most is returned.
+///
+pub fn region_largest_free(dimensions: Rect, occupied_list: &[Region]) -> Region
+{
+	let edge_list = |size: u32, edge_fn: &dyn Fn(&Region) -> (u32, u32)|
+	{
+		let mut edge_list = vec![0, size];
+		for region in occupied_list
+		{
+			let (start, end) = edge_fn(region);
+			edge_list.push(start.min(size));
+			edge_list.push(end.min(size));
+		}
+		edge_list.sort();
+		edge_list.dedup();
+		edge_list
+	};
+	let x_list = edge_list(dimensions.width, &|region| (region.x, region.right()));
+	let y_list = edge_list(dimensions.height, &|region| (region.y, region.bottom()));
+	let column_count = x_list.len() - 1;
+	let row_count = y_list.len() - 1;
+
+	// Mark the cells of the compressed grid that are covered by a region.
+	let mut occupied_grid = vec![false; column_count * row_count];
+	for region in occupied_list
+	{
+		let column_start = x_list.binary_search(&region.x.min(dimensions.width)).unwrap();
+		let column_end = x_list.binary_search(&region.right().min(dimensions.width)).unwrap();
+		let row_start = y_list.binary_search(&region.y.min(dimensions.height)).unwrap();
+		let row_end = y_list.binary_search(&region.bottom().min(dimensions.height)).unwrap();
+		for row in row_start..row_end
+		{
+			for column in column_start..column_end
+			{
+				occupied_grid[row * column_count + column] = true;
+			}
+		}
+	}
+
+	// Find the largest rectangle under the histogram of free heights ending at each row.
+	let mut best = Region::new(0, 0, 0, 0);
+	let mut best_area = 0;
+	let mut height_list = vec![0; column_count];
+	let mut stack: Vec<(usize, u32)> = Vec::with_capacity(column_count + 1);
+	for row in 0..row_count
+	{
+		let row_height = y_list[row + 1] - y_list[row];
+		for column in 0..column_count
+		{
+			height_list[column] = if occupied_grid[row * column_count + column] { 0 } else { height_list[column] + row_height };
+		}
+		stack.clear();
+		for column in 0..column_count + 1
+		{
+			let height = if column < column_count { height_list[column] } else { 0 };
+			let mut start = column;
+			while let Some(&(stack_start, stack_height)) = stack.last()
+			{
+				if stack_height < height
+				{
+					break;
+				}
+				stack.pop();
+				let width = x_list[column] - x_list[stack_start];
+				let area = width as u64 * stack_height as u64;
+				let y = y_list[row + 1] - stack_height;
+				let x = x_list[stack_start];
+				if area > best_area || (area == best_area && area != 0 && (y, x) < (best.y, best.x))
+				{
+					best = Region::new(x, y, width, stack_height);
+					best_area = area;
+				}
+				start = stack_start;
+			}
+			stack.push((start, height));
+		}
+	}
+	best
+}
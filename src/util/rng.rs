@@ -0,0 +1,55 @@
+/// A small, seeded pseudo-random number generator.
+///
+/// This is a SplitMix64 generator. It only uses integer arithmetic, so the same seed always yields
+/// the same numbers on every platform. Randomized generators should use it with a user given seed.
+///
+#[derive(Clone, Debug)]
+pub struct Rng
+{
+	state: u64,
+}
+
+impl Rng
+{
+	/// Creates a generator with the given seed.
+	pub fn new(seed: u64) -> Self
+	{
+		Rng
+		{
+			state: seed,
+		}
+	}
+
+	/// Returns the next random number.
+	pub fn next_u64(&mut self) -> u64
+	{
+		self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+		let mut value = self.state;
+		value = (value ^ (value >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+		value = (value ^ (value >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+		value ^ (value >> 31)
+	}
+
+	/// Returns a random number in the range `0..bound`.
+	pub fn below(&mut self, bound: usize) -> usize
+	{
+		assert!(bound != 0);
+		((self.next_u64() as u128 * bound as u128) >> 64) as usize
+	}
+
+	/// Returns a random number in the range `0.0..1.0`.
+	pub fn next_f64(&mut self) -> f64
+	{
+		(self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+	}
+
+	/// Shuffles the given list in place.
+	pub fn shuffle<T>(&mut self, list: &mut [T])
+	{
+		for index in (1..list.len()).rev()
+		{
+			let other = self.below(index + 1);
+			list.swap(index, other);
+		}
+	}
+}
@@ -0,0 +1,59 @@
+extern crate texture_atlas;
+
+mod corpus;
+
+use texture_atlas::{Atlas, AtlasGenerator, AtlasRect};
+use texture_atlas::gen::{BinaryTreeGenerator, PassthroughGenerator};
+
+/// Checks that every rect is placed once, inside of the bin size and without overlaps.
+fn validate<T: AtlasRect>(atlas: &Atlas<T>, width: u32, height: u32, bounded: bool)
+{
+	let mut placed_count = 0;
+	for bin_index in 0..atlas.bin_list().len()
+	{
+		let region_list = atlas.bin_occupied_list(bin_index);
+		placed_count += region_list.len();
+		for (index, region) in region_list.iter().enumerate()
+		{
+			assert!(!bounded || (region.right() <= width && region.bottom() <= height));
+			for other in &region_list[index + 1..]
+			{
+				assert!(!region.intersects(other));
+			}
+		}
+	}
+	assert_eq!(placed_count, atlas.rect_list().len());
+}
+
+fn validate_corpora<G: AtlasGenerator>(generator: &G, bounded: bool)
+{
+	for corpus in corpus::corpus_list()
+	{
+		for &rotate in &[false, true]
+		{
+			let atlas = Atlas::build(&corpus.rect_list, corpus.width, corpus.height, rotate).generate(generator).unwrap();
+			validate(&atlas, corpus.width, corpus.height, bounded);
+			assert!(atlas.bin_list().len() >= atlas.lower_bound().unwrap());
+		}
+	}
+}
+
+#[test]
+fn corpora_reproducible()
+{
+	let first: Vec<_> = corpus::power_law(50, 1).rect_list.iter().map(|rect| (rect.width, rect.height)).collect();
+	let second: Vec<_> = corpus::power_law(50, 1).rect_list.iter().map(|rect| (rect.width, rect.height)).collect();
+	assert_eq!(first, second);
+}
+
+#[test]
+fn corpora_passthrough()
+{
+	validate_corpora(&PassthroughGenerator, false);
+}
+
+#[test]
+fn corpora_binary_tree()
+{
+	validate_corpora(&BinaryTreeGenerator::new(), true);
+}
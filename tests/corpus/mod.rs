@@ -0,0 +1,169 @@
+//! Reproducible rect corpora shared by the tests and benchmarks.
+//!
+//! Every corpus is generated from a fixed seed, so the same rects are produced on every run.
+
+#![allow(dead_code)]
+
+use texture_atlas::util::{Rect, Rng};
+
+/// A list of rects along with the bin size to pack them into.
+pub struct Corpus
+{
+	pub name: String,
+	pub width: u32,
+	pub height: u32,
+	pub rect_list: Vec<Rect>,
+}
+
+/// Returns a random number in the inclusive range.
+fn uniform(rng: &mut Rng, min: u32, max: u32) -> u32
+{
+	min + rng.below((max - min + 1) as usize) as u32
+}
+
+/// Squares that all have the same size.
+pub fn uniform_squares(count: usize, size: u32, bin_size: u32) -> Corpus
+{
+	Corpus
+	{
+		name: format!("squares-{}", size),
+		width: bin_size,
+		height: bin_size,
+		rect_list: vec![Rect::new(size, size); count],
+	}
+}
+
+/// Sprites whose sizes follow a power law, so that most are small and few are large.
+pub fn power_law(count: usize, seed: u64) -> Corpus
+{
+	let mut rng = Rng::new(seed);
+	let rect_list = (0..count).map(|_|
+	{
+		let size = (8.0 * (1.0 - rng.next_f64()).powf(-1.0 / 1.5)).min(256.0) as u32;
+		let aspect = 0.5 + rng.next_f64() * 1.5;
+		Rect::new(size, ::std::cmp::max((size as f64 * aspect) as u32, 1))
+	}).collect();
+	Corpus
+	{
+		name: "power-law".to_owned(),
+		width: 1024,
+		height: 1024,
+		rect_list,
+	}
+}
+
+/// Glyphs of a few font sizes, with similar heights and varying widths.
+pub fn glyphs(count: usize, seed: u64) -> Corpus
+{
+	let mut rng = Rng::new(seed);
+	let size_list = [12, 16, 24, 32];
+	let rect_list = (0..count).map(|_|
+	{
+		let size = size_list[rng.below(size_list.len())];
+		Rect::new(uniform(&mut rng, size / 4, size), uniform(&mut rng, size * 3 / 4, size + size / 4))
+	}).collect();
+	Corpus
+	{
+		name: "glyphs".to_owned(),
+		width: 512,
+		height: 512,
+		rect_list,
+	}
+}
+
+/// Long and thin rects, either horizontal or vertical.
+pub fn thin(count: usize, seed: u64) -> Corpus
+{
+	let mut rng = Rng::new(seed);
+	let rect_list = (0..count).map(|_|
+	{
+		let (long, short) = (uniform(&mut rng, 32, 256), uniform(&mut rng, 1, 4));
+		if rng.below(2) == 0 { Rect::new(long, short) } else { Rect::new(short, long) }
+	}).collect();
+	Corpus
+	{
+		name: "thin".to_owned(),
+		width: 512,
+		height: 512,
+		rect_list,
+	}
+}
+
+/// Berkey and Wang classes I to VI, where sizes are uniform up to a maximum in a square bin.
+///
+/// These are generated from the class definitions, not taken from the published instance files.
+///
+pub fn berkey_wang(class: u32, count: usize, seed: u64) -> Corpus
+{
+	let (max, bin_size) = match class
+	{
+		1 => (10, 10),
+		2 => (10, 30),
+		3 => (35, 40),
+		4 => (35, 100),
+		5 => (100, 100),
+		6 => (100, 300),
+		_ => panic!("Berkey and Wang classes range from 1 to 6"),
+	};
+	let mut rng = Rng::new(seed);
+	let rect_list = (0..count).map(|_| Rect::new(uniform(&mut rng, 1, max), uniform(&mut rng, 1, max))).collect();
+	Corpus
+	{
+		name: format!("berkey-wang-{}", class),
+		width: bin_size,
+		height: bin_size,
+		rect_list,
+	}
+}
+
+/// Martello and Vigo classes VII to X, mixing four types of rects in 100x100 bins.
+///
+/// Each class picks its own type with a 70% chance and every other type with a 10% chance. The
+/// types are wide, tall, large and small rects. These are generated from the class definitions,
+/// not taken from the published instance files.
+///
+pub fn martello_vigo(class: u32, count: usize, seed: u64) -> Corpus
+{
+	assert!(class >= 7 && class <= 10, "Martello and Vigo classes range from 7 to 10");
+	let size = 100;
+	let mut rng = Rng::new(seed);
+	let rect_list = (0..count).map(|_|
+	{
+		let roll = rng.below(10) as u32;
+		let kind = if roll < 7 { class - 7 } else { (class - 7 + roll - 6) % 4 };
+		match kind
+		{
+			0 => Rect::new(uniform(&mut rng, size * 2 / 3, size), uniform(&mut rng, 1, size / 2)),
+			1 => Rect::new(uniform(&mut rng, 1, size / 2), uniform(&mut rng, size * 2 / 3, size)),
+			2 => Rect::new(uniform(&mut rng, size / 2, size), uniform(&mut rng, size / 2, size)),
+			_ => Rect::new(uniform(&mut rng, 1, size / 2), uniform(&mut rng, 1, size / 2)),
+		}
+	}).collect();
+	Corpus
+	{
+		name: format!("martello-vigo-{}", class),
+		width: size,
+		height: size,
+		rect_list,
+	}
+}
+
+/// Returns every corpus.
+pub fn corpus_list() -> Vec<Corpus>
+{
+	let mut corpus_list = vec![
+		uniform_squares(500, 32, 512),
+		power_law(500, 1),
+		glyphs(1000, 2),
+		thin(300, 3),
+	];
+	for class in 1..7
+	{
+		corpus_list.push(berkey_wang(class, 100, class as u64));
+	}
+	for class in 7..11
+	{
+		corpus_list.push(martello_vigo(class, 100, class as u64));
+	}
+	corpus_list
+}
@@ -0,0 +1,142 @@
+extern crate texture_atlas;
+
+use texture_atlas::dynamic::{AtlasCache, DynamicAtlas};
+use texture_atlas::gen::BinaryTreeGenerator;
+
+#[test]
+fn dynamic_allocate()
+{
+	let mut atlas = DynamicAtlas::new(64, 64);
+	assert!(atlas.allocate(0, 16).is_none());
+	assert!(atlas.allocate(65, 16).is_none());
+
+	let id_list: Vec<_> = (0..16).map(|_| atlas.allocate(16, 16).unwrap()).collect();
+	assert_eq!(atlas.page_list().len(), 1);
+	assert_eq!(atlas.page_list()[0].part_list().len(), 16);
+
+	// Every allocation is disjoint.
+	for (index, &id) in id_list.iter().enumerate()
+	{
+		let (page, region) = atlas.region(id).unwrap();
+		for &other in &id_list[index + 1..]
+		{
+			let (other_page, other_region) = atlas.region(other).unwrap();
+			assert!(page != other_page || !region.intersects(&other_region));
+		}
+	}
+
+	// A full page grows the page list.
+	let id = atlas.allocate(16, 16).unwrap();
+	assert_eq!(atlas.region(id).unwrap().0, 1);
+	assert_eq!(atlas.page_list().len(), 2);
+}
+
+#[test]
+fn dynamic_deallocate()
+{
+	let mut atlas = DynamicAtlas::new(64, 64).max_pages(1);
+	let id_list: Vec<_> = (0..16).map(|_| atlas.allocate(16, 16).unwrap()).collect();
+	assert!(atlas.allocate(16, 16).is_none());
+
+	// Freed space is coalesced so that the whole page can be reused.
+	for &id in &id_list
+	{
+		atlas.deallocate(id);
+	}
+	assert!(atlas.region(id_list[0]).is_none());
+	assert_eq!(atlas.page_list()[0].part_list().len(), 0);
+
+	let id = atlas.allocate(64, 64).unwrap();
+	assert_eq!(atlas.region(id).unwrap().1.width, 64);
+	assert_eq!(atlas.alloc_list().len(), 1);
+
+	// Stale identifiers do not refer to the allocation that reused their index.
+	let stale = id_list.iter().cloned().find(|stale| stale.index() == id.index()).unwrap();
+	assert!(stale != id && stale.generation() < id.generation());
+	assert!(atlas.region(stale).is_none());
+	atlas.deallocate(stale);
+	assert!(atlas.region(id).is_some());
+	assert_eq!(atlas.alloc_list()[0].0, id);
+}
+
+#[test]
+fn dynamic_cache()
+{
+	let mut cache = AtlasCache::new(DynamicAtlas::new(32, 32).max_pages(1));
+	for key in 0..4
+	{
+		assert!(cache.insert(key, 16, 16).is_some());
+	}
+	assert_eq!(cache.len(), 4);
+
+	// Every rect is in use during this frame, so nothing can be evicted.
+	assert!(cache.insert(4, 16, 16).is_none());
+	assert!(cache.invalidated_take().is_empty());
+
+	cache.frame_next();
+	let region_used = cache.get(&0).unwrap();
+	assert!(cache.get(&1).is_some());
+	cache.frame_next();
+	assert!(cache.get(&0).is_some());
+
+	// Rects 2 and 3 were last used in the first frame, rect 2 having been allocated first.
+	let region = cache.insert(4, 16, 16).unwrap();
+	let invalidated = cache.invalidated_take();
+	assert_eq!(invalidated.len(), 1);
+	assert_eq!(invalidated[0].0, 2);
+	assert_eq!(invalidated[0].2.x, region.1.x);
+	assert_eq!(invalidated[0].2.y, region.1.y);
+	assert!(cache.get(&2).is_none());
+	assert_eq!(cache.get(&0).unwrap().1.x, region_used.1.x);
+	assert!(cache.invalidated_take().is_empty());
+
+	// Rects that can never fit do not evict anything.
+	cache.frame_next();
+	assert!(cache.insert(5, 64, 64).is_none());
+	assert!(cache.insert(5, 0, 16).is_none());
+	assert!(cache.invalidated_take().is_empty());
+	assert_eq!(cache.len(), 4);
+}
+
+#[test]
+fn dynamic_compact()
+{
+	let mut atlas = DynamicAtlas::new(64, 64);
+	let id_list: Vec<_> = (0..32).map(|_| atlas.allocate(16, 16).unwrap()).collect();
+	assert_eq!(atlas.page_list().len(), 2);
+
+	// Free half of every page.
+	for &id in id_list.iter().step_by(2)
+	{
+		atlas.deallocate(id);
+	}
+	let region_list: Vec<_> = id_list.iter().map(|&id| atlas.region(id)).collect();
+
+	let move_list = atlas.compact(&BinaryTreeGenerator::new());
+	assert_eq!(atlas.page_list().len(), 1);
+	assert_eq!(move_list.len(), 8);
+	for alloc_move in &move_list
+	{
+		let source = region_list[alloc_move.id.index()].unwrap();
+		assert_eq!((alloc_move.source_page, alloc_move.source_x, alloc_move.source_y), (source.0, source.1.x, source.1.y));
+
+		let (page, region) = atlas.region(alloc_move.id).unwrap();
+		assert_eq!((alloc_move.target_page, alloc_move.target_x, alloc_move.target_y), (page, region.x, region.y));
+	}
+
+	// Rects in the kept page stay in place.
+	for (&id, region) in id_list.iter().zip(&region_list)
+	{
+		if let Some((0, region)) = *region
+		{
+			assert_eq!(atlas.region(id).unwrap().1.x, region.x);
+		}
+	}
+
+	// Everything is packed, so a second compaction does not move anything.
+	assert!(atlas.compact(&BinaryTreeGenerator::new()).is_empty());
+
+	// The remaining page is full, so leaves were rebuilt without any free space.
+	let id = atlas.allocate(16, 16).unwrap();
+	assert_eq!(atlas.region(id).unwrap().0, 1);
+}
@@ -2,7 +2,8 @@ extern crate image;
 extern crate texture_atlas;
 
 use image::{DynamicImage, GenericImage, Rgba};
-use texture_atlas::Atlas;
+use texture_atlas::{Atlas, OwnedAtlas};
+use texture_atlas::gen::BinaryTreeGenerator;
 use texture_atlas::util::Rect;
 
 fn image_equal(image1: DynamicImage, image2: DynamicImage) -> bool
@@ -71,3 +72,196 @@ fn image_spread()
 {
 	// 5 images. 3 images in 1 bin, 2 in another.
 }
+
+#[test]
+fn image_array()
+{
+	let rect_list = vec![DynamicImage::new_rgba8(32, 32), DynamicImage::new_rgba8(64, 48)];
+	let atlas = Atlas::build(&rect_list, 64, 64, false).array().generate(&BinaryTreeGenerator::new()).unwrap();
+	assert_eq!(atlas.bin_list().len(), 2);
+	assert_eq!(atlas.as_image_array().dimensions(), (64, 128));
+
+	let ktx2 = atlas.as_ktx2();
+	assert_eq!(&ktx2[..12], &[0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A]);
+
+	// Width, height and layer count in the header.
+	assert_eq!(&ktx2[20..24], &[64, 0, 0, 0]);
+	assert_eq!(&ktx2[24..28], &[64, 0, 0, 0]);
+	assert_eq!(&ktx2[32..36], &[2, 0, 0, 0]);
+	assert_eq!(ktx2.len(), 196 + 64 * 64 * 2 * 4);
+}
+
+#[test]
+fn image_mip_chain()
+{
+	let red = Rgba::<u8> { data: [255, 0, 0, 255] };
+	let blue = Rgba::<u8> { data: [0, 0, 255, 255] };
+	let mut image_red = DynamicImage::new_rgba8(6, 6);
+	let mut image_blue = DynamicImage::new_rgba8(6, 6);
+	for x in 0..6
+	{
+		for y in 0..6
+		{
+			image_red.put_pixel(x, y, red);
+			image_blue.put_pixel(x, y, blue);
+		}
+	}
+
+	let rect_list = vec![image_red, image_blue];
+	let atlas = Atlas::build(&rect_list, 32, 32, false).mip_levels(3).generate(&BinaryTreeGenerator::new()).unwrap();
+	assert_eq!((atlas.alignment(), atlas.padding()), (4, 8));
+	assert_eq!(atlas.bin_list().len(), 1);
+
+	let chain = &atlas.as_mip_chains(3)[0];
+	assert_eq!(chain.len(), 3);
+	assert_eq!(chain[0].dimensions(), (32, 16));
+	assert_eq!(chain[2].dimensions(), (8, 4));
+
+	// Padding between both rects is dilated by the closest rect.
+	assert_eq!(chain[0].get_pixel(8, 2), red);
+	assert_eq!(chain[0].get_pixel(15, 2), blue);
+
+	// Even at the last level, neither rect has bled into the other.
+	assert_eq!(chain[2].get_pixel(1, 1), red);
+	assert_eq!(chain[2].get_pixel(2, 1), red);
+	assert_eq!(chain[2].get_pixel(3, 1), blue);
+	assert_eq!(chain[2].get_pixel(4, 1), blue);
+}
+
+#[test]
+fn image_deterministic()
+{
+	let rect_list = vec![Rect::new(32, 16), Rect::new(16, 32), Rect::new(32, 16), Rect::new(16, 16), Rect::new(16, 32), Rect::new(16, 16), Rect::new(48, 8)];
+	let atlas = Atlas::build(&rect_list, 64, 64, true).generate(&BinaryTreeGenerator::new()).unwrap();
+
+	// FNV-1a hash of the generated pixels.
+	let mut hash: u64 = 0xCBF2_9CE4_8422_2325;
+	for byte in atlas.as_colors()[0].to_rgba().into_raw()
+	{
+		hash ^= byte as u64;
+		hash = hash.wrapping_mul(0x0000_0100_0000_01B3);
+	}
+	assert_eq!(hash, 0x3C72_9CF8_ACDB_4225);
+}
+
+/// Composes a bin one pixel at a time, the way images were composed originally.
+fn compose_reference(rect_list: &[DynamicImage], atlas: &Atlas<DynamicImage>, bin_index: usize) -> DynamicImage
+{
+	let bin = &atlas.bin_list()[bin_index];
+	let mut image = DynamicImage::new_rgba8(bin.dimensions().width, bin.dimensions().height);
+	for part in bin.part_list()
+	{
+		let texture = &rect_list[part.rect_index];
+		for x in 0..texture.width()
+		{
+			for y in 0..texture.height()
+			{
+				let pixel = texture.get_pixel(x, y);
+				if !part.rotate
+				{
+					image.put_pixel(part.x + x, part.y + y, pixel);
+				}
+				else
+				{
+					image.put_pixel(part.x + (texture.height() - 1 - y), part.y + x, pixel);
+				}
+			}
+		}
+	}
+	image
+}
+
+#[test]
+fn image_compose_identical()
+{
+	// Different formats and sizes larger than a transpose tile, some of which are rotated.
+	let mut rect_list = vec![
+		DynamicImage::new_rgba8(70, 33),
+		DynamicImage::new_rgb8(40, 90),
+		DynamicImage::new_luma8(17, 5),
+		DynamicImage::new_luma_a8(33, 65),
+	];
+	for (index, image) in rect_list.iter_mut().enumerate()
+	{
+		for x in 0..image.width()
+		{
+			for y in 0..image.height()
+			{
+				let value = (x * 7 + y * 13 + index as u32 * 29) as u8;
+				image.put_pixel(x, y, Rgba::<u8> { data: [value, value ^ 0x5A, value / 2, 255 - value] });
+			}
+		}
+	}
+
+	let atlas = Atlas::build(&rect_list, 128, 128, true).generate(&BinaryTreeGenerator::new()).unwrap();
+	assert!(atlas.bin_list().iter().any(|bin| bin.part_list().iter().any(|part| part.rotate)));
+	for (bin_index, image) in atlas.as_images().into_iter().enumerate()
+	{
+		assert!(image_equal(image, compose_reference(&rect_list, &atlas, bin_index)));
+	}
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn image_parallel()
+{
+	let image_list: Vec<DynamicImage> = (0..12).map(|index|
+	{
+		let mut image = DynamicImage::new_rgba8(10 + index * 3, 30 - index);
+		image.put_pixel(1, 2, Rgba([index as u8 * 20, 0, 255, 255]));
+		image
+	}).collect();
+	let atlas = Atlas::build(&image_list, 64, 64, true).mip_levels(2).generate(&BinaryTreeGenerator::new()).unwrap();
+	assert!(atlas.bin_list().len() > 1);
+
+	// Parallel composition gives the same images as sequential composition.
+	for (image, par_image) in atlas.as_images().into_iter().zip(atlas.par_as_images())
+	{
+		assert!(image_equal(image, par_image));
+	}
+	for (buffer, par_buffer) in atlas.as_rgba_images().into_iter().zip(atlas.par_as_rgba_images())
+	{
+		assert_eq!(buffer.into_raw(), par_buffer.into_raw());
+	}
+	for (image, par_image) in atlas.as_colors().into_iter().zip(atlas.par_as_colors())
+	{
+		assert!(image_equal(image, par_image));
+	}
+	let chain_list = atlas.as_mip_chains(2);
+	let par_chain_list = atlas.par_as_mip_chains(2);
+	assert_eq!(chain_list.len(), par_chain_list.len());
+	for (chain, par_chain) in chain_list.into_iter().zip(par_chain_list)
+	{
+		for (image, par_image) in chain.into_iter().zip(par_chain)
+		{
+			assert!(image_equal(image, par_image));
+		}
+	}
+}
+
+#[test]
+fn image_owned()
+{
+	let color_list = [Rgba([255, 0, 0, 255]), Rgba([0, 255, 0, 255]), Rgba([0, 0, 255, 255])];
+	let image_list: Vec<DynamicImage> = color_list.iter().enumerate().map(|(index, &color)|
+	{
+		let mut image = DynamicImage::new_rgba8(8 + index as u32 * 4, 12);
+		for x in 0..image.width()
+		{
+			for y in 0..image.height()
+			{
+				image.put_pixel(x, y, color);
+			}
+		}
+		image
+	}).collect();
+
+	let atlas = Atlas::build(&image_list, 32, 32, true).generate(&BinaryTreeGenerator::new()).unwrap();
+	let owned: OwnedAtlas<DynamicImage> = Atlas::build_owned(image_list.clone(), 32, 32, true).generate(&BinaryTreeGenerator::new()).unwrap();
+	assert!(image_equal(owned.as_images().remove(0), atlas.as_images().remove(0)));
+	assert_eq!(owned.as_ktx2(), atlas.as_ktx2());
+
+	// Images are composed on another thread along with the rects they are composed of.
+	let image = std::thread::spawn(move || owned.bin_as_image(0)).join().unwrap();
+	assert!(image_equal(image, atlas.bin_as_image(0)));
+}
@@ -0,0 +1,377 @@
+#[cfg(feature = "image")]
+extern crate image;
+#[cfg(feature = "serde")]
+extern crate serde_json;
+extern crate texture_atlas;
+
+use texture_atlas::Atlas;
+use texture_atlas::gen::BinaryTreeGenerator;
+use texture_atlas::layout::{AtlasLayout, BinaryError, ImportError, ImportedAtlas, LayoutError, LayoutReader, LayoutWriter, Rotation,
+	Trim};
+use texture_atlas::util::{Rect, Rng};
+
+fn rect_list_random(seed: u64) -> Vec<Rect>
+{
+	let mut rng = Rng::new(seed);
+	(0..40).map(|_| Rect::new(1 + rng.below(30) as u32, 1 + rng.below(30) as u32)).collect()
+}
+
+fn placement_list(layout: &AtlasLayout) -> Vec<(usize, usize, u32, u32, bool)>
+{
+	let mut placement_list = Vec::new();
+	for (bin_index, bin) in layout.bin_list().iter().enumerate()
+	{
+		for part in bin.part_list()
+		{
+			placement_list.push((part.rect_index, bin_index, part.x, part.y, part.rotate));
+		}
+	}
+	placement_list
+}
+
+#[test]
+fn layout_apply()
+{
+	let rect_list = rect_list_random(1);
+	let atlas = Atlas::build(&rect_list, 64, 64, true).reserve(0, 0, 2, 2).mip_levels(2).generate(&BinaryTreeGenerator::new()).unwrap();
+	let layout = atlas.layout();
+	assert_eq!(layout.rect_list().len(), rect_list.len());
+
+	// Applying the layout gives the same atlas.
+	let applied = layout.apply(&rect_list).unwrap();
+	assert_eq!(placement_list(&applied.layout()), placement_list(&layout));
+	assert_eq!((applied.alignment(), applied.padding()), (atlas.alignment(), atlas.padding()));
+	assert_eq!(applied.bin_reserved_list(0).len(), 1);
+	assert_eq!(applied.stats().wasted_area, atlas.stats().wasted_area);
+	assert!(layout.apply_owned(rect_list.clone()).unwrap().is_owned());
+
+	// Rects must match the layout.
+	assert_eq!(layout.apply(&rect_list[1..]).err(), Some(LayoutError::RectCount(40, 39)));
+	let mut changed_list = rect_list.clone();
+	changed_list[3].width += 1;
+	assert_eq!(layout.apply(&changed_list).err(), Some(LayoutError::RectSize(3)));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn layout_serde()
+{
+	let rect_list = rect_list_random(2);
+	let atlas = Atlas::build(&rect_list, 64, 64, true).generate(&BinaryTreeGenerator::new()).unwrap();
+	let text = serde_json::to_string(&atlas.layout()).unwrap();
+	let layout: AtlasLayout = serde_json::from_str(&text).unwrap();
+	assert_eq!(placement_list(&layout), placement_list(&atlas.layout()));
+	assert!(layout.apply(&rect_list).is_ok());
+
+	// Layout types round trip on their own.
+	let bin = &atlas.bin_list()[0];
+	let text = serde_json::to_string(bin).unwrap();
+	let loaded: texture_atlas::AtlasBin = serde_json::from_str(&text).unwrap();
+	assert_eq!(loaded.part_list().len(), bin.part_list().len());
+	assert_eq!((loaded.dimensions().width, loaded.dimensions().height), (bin.dimensions().width, bin.dimensions().height));
+
+	// Layouts referencing rects that do not exist are rejected.
+	let text = serde_json::to_string(&atlas.layout()).unwrap().replacen("\"rect_index\":", "\"rect_index\":1000", 1);
+	let layout: AtlasLayout = serde_json::from_str(&text).unwrap();
+	assert_eq!(layout.apply(&rect_list).err(), Some(LayoutError::PartIndex(0)));
+
+	// Layouts with parts outside of their bin are rejected.
+	let text = serde_json::to_string(&atlas.layout()).unwrap().replacen("\"x\":", "\"x\":1000", 1);
+	let layout: AtlasLayout = serde_json::from_str(&text).unwrap();
+	assert_eq!(layout.apply(&rect_list).err(), Some(LayoutError::PartBounds(0)));
+
+	// Layouts reserving regions in bins that do not exist are rejected.
+	let atlas = Atlas::build(&rect_list, 64, 64, true).reserve_bin(0, 0, 0, 2, 2).generate(&BinaryTreeGenerator::new()).unwrap();
+	let text = serde_json::to_string(&atlas.layout()).unwrap().replacen("\"reserved_list\":[[0,", "\"reserved_list\":[[1000,", 1);
+	let layout: AtlasLayout = serde_json::from_str(&text).unwrap();
+	assert_eq!(layout.apply(&rect_list).err(), Some(LayoutError::ReservedBin(0)));
+
+	// Layouts without a valid alignment are rejected.
+	let text = serde_json::to_string(&atlas.layout()).unwrap().replacen("\"alignment\":1", "\"alignment\":0", 1);
+	let layout: AtlasLayout = serde_json::from_str(&text).unwrap();
+	assert_eq!(layout.apply(&rect_list).err(), Some(LayoutError::Alignment));
+}
+
+#[cfg(feature = "image")]
+#[test]
+fn layout_render()
+{
+	use image::{DynamicImage, GenericImage, Rgba};
+
+	let image_list: Vec<DynamicImage> = (0..6).map(|index|
+	{
+		let mut image = DynamicImage::new_rgba8(4 + index * 3, 10 - index);
+		let color = Rgba([index as u8 * 40, 255 - index as u8 * 40, 0, 255]);
+		for x in 0..image.width()
+		{
+			for y in 0..image.height()
+			{
+				image.put_pixel(x, y, color);
+			}
+		}
+		image
+	}).collect();
+
+	let atlas = Atlas::build(&image_list, 32, 32, true).generate(&BinaryTreeGenerator::new()).unwrap();
+	let layout = atlas.layout();
+
+	// Images rendered from a saved layout are identical to the generated atlas.
+	let applied = layout.apply(&image_list).unwrap();
+	assert_eq!(applied.as_images()[0].raw_pixels(), atlas.as_images()[0].raw_pixels());
+}
+
+#[test]
+fn layout_binary()
+{
+	let rect_list = rect_list_random(3);
+	let atlas = Atlas::build(&rect_list, 64, 64, true).generate(&BinaryTreeGenerator::new()).unwrap();
+	let name_string_list: Vec<String> = (0..rect_list.len() - 1).map(|index| format!("sprite_{}", index)).collect();
+	let name_list: Vec<&str> = name_string_list.iter().map(String::as_str).collect();
+	let trim_list = vec![Trim::new(2, 3, rect_list[0].width + 5, rect_list[0].height + 4)];
+	let data = LayoutWriter::new().name_list(&name_list).trim_list(&trim_list).write(&atlas);
+
+	let reader = LayoutReader::new(&data).unwrap();
+	assert_eq!(reader.version(), 1);
+	assert_eq!(reader.bin_count(), atlas.bin_list().len());
+	assert_eq!(reader.region_count(), rect_list.len());
+	for (bin_index, bin) in atlas.bin_list().iter().enumerate()
+	{
+		assert_eq!((reader.bin(bin_index).width, reader.bin(bin_index).height), (bin.dimensions().width, bin.dimensions().height));
+	}
+	let mut region_index = 0;
+	for (bin_index, bin) in atlas.bin_list().iter().enumerate()
+	{
+		for part in bin.part_list()
+		{
+			let region = reader.region(region_index);
+			let rect = rect_list[part.rect_index];
+			assert_eq!((region.rect_index, region.bin, region.x, region.y), (part.rect_index, bin_index, part.x, part.y));
+			assert_eq!((region.width, region.height, region.rotated), (rect.width, rect.height, part.rotate));
+			assert_eq!(region.name, name_list.get(part.rect_index).cloned().unwrap_or(""));
+			region_index += 1;
+		}
+	}
+
+	// Regions are found by name, with trims written for the rects that have them.
+	let region = reader.region_find("sprite_0").unwrap();
+	assert_eq!(region.trim, trim_list[0]);
+	assert!(region.trim.is_trimmed(region.width, region.height));
+	let region = reader.region_find("sprite_1").unwrap();
+	assert!(!region.trim.is_trimmed(region.width, region.height));
+	assert!(reader.region_find("sprite_1000").is_none());
+
+	// Imported binary layouts give back the placements of the atlas.
+	let imported = ImportedAtlas::from_binary(&reader).unwrap();
+	assert_eq!(imported.sprite_list()[0].name, "sprite_0");
+	assert_eq!(imported.sprite_list()[0].trim, trim_list[0]);
+	assert_eq!(imported.sprite_list()[rect_list.len() - 1].name, (rect_list.len() - 1).to_string());
+	let layout = imported.layout();
+	let mut imported_list = placement_list(&layout);
+	let mut atlas_list = placement_list(&atlas.layout());
+	imported_list.sort();
+	atlas_list.sort();
+	assert_eq!(imported_list, atlas_list);
+	assert!(layout.apply(&rect_list).is_ok());
+
+	// Invalid bytes are rejected.
+	assert_eq!(LayoutReader::new(b"PNG\0 not a layout at all").err(), Some(BinaryError::Magic));
+	assert_eq!(LayoutReader::new(&data[..data.len() - 1]).err(), Some(BinaryError::Truncated));
+	let mut newer = data.clone();
+	newer[4] = 2;
+	assert_eq!(LayoutReader::new(&newer).err(), Some(BinaryError::Version(2)));
+	let mut invalid = data.clone();
+	let last = invalid.len() - 1;
+	invalid[last] = 0xFF;
+	assert!(match LayoutReader::new(&invalid) { Err(BinaryError::Name(_)) => true, _ => false });
+
+	// An empty atlas is still a valid layout.
+	let empty_list: Vec<Rect> = Vec::new();
+	let data = LayoutWriter::new().write(&Atlas::new(&empty_list));
+	assert_eq!(LayoutReader::new(&data).unwrap().region_count(), 0);
+}
+
+#[test]
+fn layout_import()
+{
+	let rect_list = rect_list_random(4);
+	let atlas = Atlas::build(&rect_list, 64, 64, true).generate(&BinaryTreeGenerator::new()).unwrap();
+	let name_string_list: Vec<String> = (0..rect_list.len()).map(|index| format!("sprite \"{}\"", index)).collect();
+	let name_list: Vec<&str> = name_string_list.iter().map(String::as_str).collect();
+	let trim_list = vec![Trim::new(2, 3, rect_list[0].width + 5, rect_list[0].height + 4)];
+	let writer = LayoutWriter::new().name_list(&name_list).trim_list(&trim_list);
+
+	// Every bin of TexturePacker metadata is read back and merged.
+	let mut imported = ImportedAtlas::default();
+	for bin_index in 0..atlas.bin_list().len()
+	{
+		let text = writer.write_texture_packer(&atlas, bin_index, &format!("page_{}.png", bin_index));
+		imported = imported.merge(ImportedAtlas::from_texture_packer(&text).unwrap());
+	}
+	assert_eq!(imported.page_list().len(), atlas.bin_list().len());
+	assert_eq!(imported.sprite_list().len(), rect_list.len());
+	for (bin_index, bin) in atlas.bin_list().iter().enumerate()
+	{
+		assert_eq!(imported.page_list()[bin_index].image_name, format!("page_{}.png", bin_index));
+		for part in bin.part_list()
+		{
+			let sprite = imported.sprite_find(name_list[part.rect_index]).unwrap();
+			let rect = rect_list[part.rect_index];
+			assert_eq!((sprite.page, sprite.x, sprite.y, sprite.width, sprite.height), (bin_index, part.x, part.y, rect.width, rect.height));
+			assert_eq!(sprite.rotation, if part.rotate { Rotation::Clockwise } else { Rotation::None });
+		}
+	}
+	assert_eq!(imported.sprite_find(name_list[0]).unwrap().trim, trim_list[0]);
+
+	// Rotated rects are written as the counter-clockwise rotation of libGDX, keeping their regions.
+	let page_name_list: Vec<String> = (0..atlas.bin_list().len()).map(|index| format!("page_{}.png", index)).collect();
+	let page_name_list: Vec<&str> = page_name_list.iter().map(String::as_str).collect();
+	let rotated = atlas.bin_list().iter().flat_map(|bin| bin.part_list()).any(|part| part.rotate);
+	assert!(rotated);
+	let imported = ImportedAtlas::from_libgdx(&writer.write_libgdx(&atlas, &page_name_list).unwrap()).unwrap();
+	for (bin_index, bin) in atlas.bin_list().iter().enumerate()
+	{
+		for part in bin.part_list()
+		{
+			let sprite = imported.sprite_find(name_list[part.rect_index]).unwrap();
+			let rect = rect_list[part.rect_index];
+			assert_eq!((sprite.page, sprite.x, sprite.y, sprite.width, sprite.height), (bin_index, part.x, part.y, rect.width, rect.height));
+			assert_eq!(sprite.rotation, if part.rotate { Rotation::CounterClockwise } else { Rotation::None });
+		}
+	}
+	assert!(writer.write_libgdx(&atlas, &page_name_list[1..]).is_none());
+	let atlas = Atlas::build(&rect_list, 64, 64, false).generate(&BinaryTreeGenerator::new()).unwrap();
+	let page_name_list: Vec<&str> = page_name_list.iter().cloned().chain(Some("extra.png")).take(atlas.bin_list().len()).collect();
+	let text = writer.write_libgdx(&atlas, &page_name_list).unwrap();
+	let imported = ImportedAtlas::from_libgdx(&text).unwrap();
+	assert_eq!(imported.page_list().len(), atlas.bin_list().len());
+	assert_eq!(imported.sprite_list().len(), rect_list.len());
+	for (bin_index, bin) in atlas.bin_list().iter().enumerate()
+	{
+		let (page, dimensions) = (&imported.page_list()[bin_index].dimensions, bin.dimensions());
+		assert_eq!((page.width, page.height), (dimensions.width, dimensions.height));
+		for part in bin.part_list()
+		{
+			let sprite = imported.sprite_find(name_list[part.rect_index]).unwrap();
+			assert_eq!((sprite.page, sprite.x, sprite.y, sprite.rotation), (bin_index, part.x, part.y, Rotation::None));
+		}
+	}
+	assert_eq!(imported.sprite_find(name_list[0]).unwrap().trim, trim_list[0]);
+}
+
+#[test]
+fn layout_import_formats()
+{
+	// The array format of TexturePacker, with a rotated and trimmed sprite.
+	let text = r#"{"frames": [
+		{"filename": "a.png", "frame": {"x": 1, "y": 2, "w": 3, "h": 4}, "rotated": true, "trimmed": true,
+			"spriteSourceSize": {"x": 1, "y": 0, "w": 3, "h": 4}, "sourceSize": {"w": 5, "h": 4}},
+		{"filename": "bé.png", "frame": {"x": 5, "y": 0, "w": 2, "h": 2}}
+	], "meta": {"image": "sheet.png", "size": {"w": 8, "h": 8}, "scale": "1"}}"#;
+	let imported = ImportedAtlas::from_texture_packer(text).unwrap();
+	let sprite = &imported.sprite_list()[0];
+	assert_eq!((sprite.x, sprite.y, sprite.width, sprite.height, sprite.rotation), (1, 2, 3, 4, Rotation::Clockwise));
+	let region = sprite.region();
+	assert_eq!((region.x, region.y, region.width, region.height), (1, 2, 4, 3));
+	assert_eq!(sprite.trim, Trim::new(1, 0, 5, 4));
+	assert_eq!(imported.sprite_list()[1].name, "b\u{e9}.png");
+	assert_eq!(imported.sprite_list()[1].trim, Trim::none(2, 2));
+	let text = r#"{"frames": {"\ud83d\ude00": {"frame": {"x": 0, "y": 0, "w": 1, "h": 1}}}, "meta": {"image": "a.png", "size": {"w": 1, "h": 1}}}"#;
+	assert_eq!(ImportedAtlas::from_texture_packer(text).unwrap().sprite_list()[0].name, "\u{1f600}");
+
+	// The current format of libGDX, with page options and a rotated region.
+	let text = "sheet.png\nsize:16,16\nfilter:Linear,Linear\npma:true\na\nbounds:0,0,4,2\noffsets:1,1,6,5\nrotate:true\nb\nbounds:2,0,3,3\nindex:2\n";
+	let imported = ImportedAtlas::from_libgdx(text).unwrap();
+	assert_eq!((imported.page_list()[0].dimensions.width, imported.page_list()[0].dimensions.height), (16, 16));
+	let sprite = imported.sprite_find("a").unwrap();
+	assert_eq!((sprite.width, sprite.height, sprite.rotation), (4, 2, Rotation::CounterClockwise));
+	assert_eq!(sprite.trim, Trim::new(1, 2, 6, 5));
+	assert_eq!(imported.sprite_find("b").unwrap().trim, Trim::none(3, 3));
+
+	// Sprites rotated counter-clockwise are rotated rects in the converted layout.
+	let layout = imported.layout();
+	assert_eq!(placement_list(&layout), vec![(0, 0, 0, 0, true), (1, 0, 2, 0, false)]);
+	assert_eq!((layout.rect_list()[0].width, layout.rect_list()[0].height), (4, 2));
+	assert!(layout.apply(layout.rect_list()).is_ok());
+
+	// Malformed metadata is rejected.
+	assert_eq!(ImportedAtlas::from_texture_packer("{\"frames\": [}").err(), Some(ImportError::Syntax(12)));
+	assert_eq!(ImportedAtlas::from_texture_packer("{\"frames\": []}").err(), Some(ImportError::Field("meta".to_string())));
+	assert_eq!(ImportedAtlas::from_texture_packer(&"[".repeat(1_000_000)).err(), Some(ImportError::Syntax(128)));
+	assert_eq!(ImportedAtlas::from_texture_packer("\"\\u+041\"").err(), Some(ImportError::Syntax(3)));
+	assert_eq!(ImportedAtlas::from_texture_packer("\"\\ud800\"").err(), Some(ImportError::Syntax(1)));
+	assert_eq!(ImportedAtlas::from_texture_packer("\"\\ud800\\u0041\"").err(), Some(ImportError::Syntax(7)));
+	assert_eq!(ImportedAtlas::from_texture_packer("\"\\udc00\"").err(), Some(ImportError::Syntax(1)));
+	assert_eq!(ImportedAtlas::from_texture_packer("[01]").err(), Some(ImportError::Syntax(1)));
+	assert_eq!(ImportedAtlas::from_texture_packer("[1.]").err(), Some(ImportError::Syntax(3)));
+	assert_eq!(ImportedAtlas::from_texture_packer("[-]").err(), Some(ImportError::Syntax(2)));
+	assert_eq!(ImportedAtlas::from_texture_packer("[1e+]").err(), Some(ImportError::Syntax(4)));
+	assert_eq!(ImportedAtlas::from_texture_packer("[+1]").err(), Some(ImportError::Syntax(1)));
+	assert_eq!(ImportedAtlas::from_texture_packer("[-0.5e-3, 0, 10E2]").err(), Some(ImportError::Field("meta".to_string())));
+	assert_eq!(ImportedAtlas::from_texture_packer("\"a\tb\"").err(), Some(ImportError::Syntax(2)));
+	assert_eq!(ImportedAtlas::from_libgdx("sheet.png\nsize: 4, 4\na\n  xy: 0, x\n").err(), Some(ImportError::Syntax(4)));
+	assert_eq!(ImportedAtlas::from_libgdx("sheet.png\nsize: 4, 4\na\n  size: 2, 2\n").err(), Some(ImportError::Field("xy".to_string())));
+	assert_eq!(ImportedAtlas::from_libgdx("sheet.png\nsize: 4, 4\na\n  xy: 3, 0\n  size: 2, 2\n").err(), Some(ImportError::Bounds("a".to_string())));
+}
+
+#[cfg(feature = "image")]
+#[test]
+fn layout_unpack()
+{
+	use image::{DynamicImage, GenericImage, Rgba};
+	use texture_atlas::util::border;
+
+	// Images with distinct pixels and transparent borders of different sizes.
+	let source_list: Vec<DynamicImage> = (0..8u32).map(|index|
+	{
+		let mut image = DynamicImage::new_rgba8(6 + index, 9 - index % 4);
+		for x in index % 3..image.width() - 1
+		{
+			for y in 1..image.height() - index % 2
+			{
+				image.put_pixel(x, y, Rgba([x as u8 * 20, y as u8 * 20, index as u8 * 30, 255]));
+			}
+		}
+		image
+	}).collect();
+	let mut image_list = Vec::new();
+	let mut trim_list = Vec::new();
+	for source in &source_list
+	{
+		let (width, height) = (source.width(), source.height());
+		let (left, right, top, bottom) = border(source);
+		let cropped = source.clone().crop(left, top, right + 1 - left, bottom + 1 - top);
+		trim_list.push(Trim::new(left, top, width, height));
+		image_list.push(cropped);
+	}
+
+	// Sprites unpacked from TexturePacker metadata match their sources, even when rotated.
+	let atlas = Atlas::build(&image_list, 32, 32, true).generate(&BinaryTreeGenerator::new()).unwrap();
+	let writer = LayoutWriter::new().trim_list(&trim_list);
+	let page_list = atlas.as_images();
+	let mut imported = ImportedAtlas::default();
+	for bin_index in 0..atlas.bin_list().len()
+	{
+		imported = imported.merge(ImportedAtlas::from_texture_packer(&writer.write_texture_packer(&atlas, bin_index, "page.png")).unwrap());
+	}
+	for (sprite, image) in imported.sprite_list().iter().zip(imported.unpack(&page_list).unwrap())
+	{
+		let source = &source_list[sprite.name.parse::<usize>().unwrap()];
+		assert_eq!(image.raw_pixels(), source.raw_pixels());
+	}
+
+	// Sprites rotated counter-clockwise are rotated back too.
+	let text = "sheet.png\nsize: 3, 2\na\n  rotate: true\n  xy: 0, 0\n  size: 2, 3\n";
+	let mut page = DynamicImage::new_rgba8(3, 2);
+	page.put_pixel(2, 0, Rgba([255, 0, 0, 255]));
+
+	// The top right of the page is the bottom right of the sprite.
+	let image = &ImportedAtlas::from_libgdx(text).unwrap().unpack(&[page]).unwrap()[0];
+	assert_eq!((image.width(), image.height()), (2, 3));
+	assert_eq!(image.get_pixel(1, 2), Rgba([255, 0, 0, 255]));
+
+	// Page images must match the metadata.
+	let text = "sheet.png\nsize: 8, 8\na\n  xy: 4, 4\n  size: 4, 4\n";
+	let imported = ImportedAtlas::from_libgdx(text).unwrap();
+	assert_eq!(imported.unpack(&[]).err(), Some(ImportError::PageCount(1, 0)));
+	assert_eq!(imported.unpack(&[DynamicImage::new_rgba8(4, 4)]).err(), Some(ImportError::PageSize(0)));
+}
@@ -0,0 +1,108 @@
+extern crate texture_atlas;
+
+use texture_atlas::online::{MaxRectsPacker, OnlinePacker, Placement, ShelfPacker, SkylinePacker};
+use texture_atlas::util::{Rect, Region, Rng};
+
+/// Inserts random rects and checks that placements are inside their bin, disjoint and match the bins.
+fn online_smoke<P: OnlinePacker>(mut packer: P, rotate: bool)
+{
+	let mut rng = Rng::new(7);
+	let rect_list: Vec<Rect> = (0..200).map(|_| Rect::new(1 + rng.below(24) as u32, 1 + rng.below(24) as u32)).collect();
+	let mut region_list: Vec<(usize, Region)> = Vec::new();
+	for (rect_index, &rect) in rect_list.iter().enumerate()
+	{
+		let (bin_index, part) = packer.insert(rect).part().unwrap();
+		assert_eq!(part.rect_index, rect_index);
+		assert!(rotate || !part.rotate);
+
+		let region = if part.rotate
+		{
+			Region::new(part.x, part.y, rect.height, rect.width)
+		}
+		else
+		{
+			Region::new(part.x, part.y, rect.width, rect.height)
+		};
+		assert!(region.right() <= 64 && region.bottom() <= 64);
+		for &(other_bin_index, other) in &region_list
+		{
+			assert!(bin_index != other_bin_index || !region.intersects(&other));
+		}
+		region_list.push((bin_index, region));
+	}
+
+	let part_count: usize = packer.bin_list().iter().map(|bin| bin.part_list().len()).sum();
+	assert_eq!(part_count, rect_list.len());
+	for (bin_index, bin) in packer.bin_list().iter().enumerate()
+	{
+		for part in bin.part_list()
+		{
+			assert_eq!(region_list[part.rect_index].0, bin_index);
+			assert_eq!((region_list[part.rect_index].1.x, region_list[part.rect_index].1.y), (part.x, part.y));
+		}
+	}
+}
+
+#[test]
+fn online_skyline()
+{
+	online_smoke(SkylinePacker::new(64, 64), false);
+	online_smoke(SkylinePacker::new(64, 64).rotate(true), true);
+}
+
+#[test]
+fn online_shelf()
+{
+	online_smoke(ShelfPacker::new(64, 64), false);
+	online_smoke(ShelfPacker::new(64, 64).rotate(true), true);
+}
+
+#[test]
+fn online_max_rects()
+{
+	online_smoke(MaxRectsPacker::new(64, 64), false);
+	online_smoke(MaxRectsPacker::new(64, 64).rotate(true), true);
+}
+
+#[test]
+fn online_unplaced()
+{
+	let mut packer = SkylinePacker::new(32, 16).max_bins(1);
+	assert!(!packer.insert(Rect::new(0, 8)).is_placed());
+	assert!(!packer.insert(Rect::new(16, 32)).is_placed());
+	assert!(packer.insert(Rect::new(32, 16)).is_placed());
+	assert!(!packer.insert(Rect::new(1, 1)).is_placed());
+	assert!(packer.bin_list().len() == 1);
+
+	// Unplaced rects still take up a rect index.
+	assert_eq!(packer.bin_list()[0].part_list()[0].rect_index, 2);
+
+	// Rects only fitting rotated are rotated when allowed.
+	let mut packer = ShelfPacker::new(32, 16).rotate(true);
+	match packer.insert(Rect::new(16, 32))
+	{
+		Placement::Placed(bin_index, part) => assert_eq!((bin_index, part.x, part.y, part.rotate), (0, 0, 0, true)),
+		Placement::Unplaced => panic!("Rect should fit rotated"),
+	}
+}
+
+#[test]
+fn online_fill()
+{
+	// Equal squares fill a bin exactly before another bin is added.
+	let mut packer_list: Vec<Box<OnlinePacker>> = vec![
+		Box::new(SkylinePacker::new(64, 64)),
+		Box::new(ShelfPacker::new(64, 64)),
+		Box::new(MaxRectsPacker::new(64, 64)),
+	];
+	for packer in &mut packer_list
+	{
+		for _ in 0..17
+		{
+			packer.insert(Rect::new(16, 16));
+		}
+		assert_eq!(packer.bin_list().len(), 2);
+		assert_eq!(packer.bin_list()[0].part_list().len(), 16);
+		assert_eq!(packer.bin_list()[0].dimensions().width, 64);
+	}
+}
@@ -1,8 +1,11 @@
 extern crate texture_atlas;
 
-use texture_atlas::{Atlas, AtlasBin, AtlasGenerator, AtlasRect};
-use texture_atlas::gen::{BinaryTreeGenerator, PassthroughGenerator};
-use texture_atlas::util::Rect;
+mod corpus;
+
+use texture_atlas::{Atlas, AtlasBin, AtlasGenerator, AtlasRect, OwnedAtlas};
+use texture_atlas::gen::{AnnealingGenerator, BinaryTreeGenerator, DynGenerator, GeneratorError, GeneratorParams,
+	GeneratorRegistry, OptimalGenerator, OrderedGenerator, PassthroughGenerator, SortPolicy, SplitPolicy};
+use texture_atlas::util::{self, Rect, Region, Rng, MAX_MIP_LEVELS};
 
 #[derive(Eq, PartialEq, PartialOrd)]
 struct SweepPart
@@ -135,17 +138,17 @@ fn smoke<T: AtlasGenerator>(generator: &T)
 
 	// Empty rect list should generate no bins.
 	let list_empty: Vec<Rect> = Vec::new();
-	let atlas = Atlas::build(&list_empty, ATLAS_WIDTH, ATLAS_HEIGHT, false).generate(generator);
+	let atlas = Atlas::build(&list_empty, ATLAS_WIDTH, ATLAS_HEIGHT, false).generate(generator).unwrap();
 	assert_eq!(atlas.bin_list().len(), 0);
 
 	// Single item rect list should always generate one bin.
 	let list_single = vec![rect_large];
-	let atlas = Atlas::build(&list_single, ATLAS_WIDTH, ATLAS_HEIGHT, false).generate(generator);
+	let atlas = Atlas::build(&list_single, ATLAS_WIDTH, ATLAS_HEIGHT, false).generate(generator).unwrap();
 	assert_eq!(atlas.bin_list().len(), 1);
 
 	// Having two large items means you cannot fit everything, so two bins.
 	let list_large = vec![rect_large, rect_large];
-	let atlas = Atlas::build(&list_large, ATLAS_WIDTH, ATLAS_HEIGHT, false).generate(generator);
+	let atlas = Atlas::build(&list_large, ATLAS_WIDTH, ATLAS_HEIGHT, false).generate(generator).unwrap();
 	assert_eq!(atlas.bin_list().len(), 2);
 	smoke_atlas(&atlas);
 }
@@ -159,7 +162,642 @@ fn test_passthrough()
 #[test]
 fn test_binary_tree()
 {
-	smoke(&BinaryTreeGenerator);
+	smoke(&BinaryTreeGenerator::new());
 
 	// TODO: Better tests specific to this generator.
 }
+
+#[test]
+fn test_optimal()
+{
+	smoke(&OptimalGenerator::new().node_limit(10_000));
+}
+
+#[test]
+fn test_annealing()
+{
+	smoke(&AnnealingGenerator::new(BinaryTreeGenerator::new()).iteration_limit(100));
+}
+
+fn assert_disjoint<T: AtlasRect>(atlas: &Atlas<T>)
+{
+	for bin_index in 0..atlas.bin_list().len()
+	{
+		let region_list = atlas.bin_occupied_list(bin_index);
+		for (index, region) in region_list.iter().enumerate()
+		{
+			for other in &region_list[index + 1..]
+			{
+				assert!(!region.intersects(other), "Bin {} has overlapping regions", bin_index);
+			}
+		}
+	}
+}
+
+fn smoke_reserved<T: AtlasGenerator>(generator: &T)
+{
+	let rect_list = vec![Rect::new(64, 64); 6];
+	let atlas = Atlas::build(&rect_list, 128, 128, false)
+		.reserve(0, 0, 1, 1)
+		.reserve_bin(1, 64, 64, 64, 64)
+		.pin(5, 1, 0, 64, false)
+		.generate(generator).unwrap();
+	assert_disjoint(&atlas);
+	assert_eq!(atlas.rect_placed_list(), vec![true; rect_list.len()]);
+
+	let part = &atlas.bin_list()[1].part_list()[0];
+	assert_eq!((part.rect_index, part.x, part.y), (5, 0, 64));
+	for (bin_index, bin) in atlas.bin_list().iter().enumerate()
+	{
+		let reserved_count = if bin_index == 1 { 2 } else { 1 };
+		assert_eq!(bin.reserved_list().len(), reserved_count);
+	}
+}
+
+#[test]
+fn test_passthrough_reserved()
+{
+	smoke_reserved(&PassthroughGenerator);
+}
+
+#[test]
+fn test_binary_tree_reserved()
+{
+	smoke_reserved(&BinaryTreeGenerator::new());
+
+	// Reserved corner leaves room for three rects per bin, except for the second bin.
+	let rect_list = vec![Rect::new(64, 64); 6];
+	let atlas = Atlas::build(&rect_list, 128, 128, false)
+		.reserve(0, 0, 1, 1)
+		.reserve_bin(1, 64, 64, 64, 64)
+		.pin(5, 1, 0, 64, false)
+		.generate(&BinaryTreeGenerator::new()).unwrap();
+	assert_eq!(atlas.bin_list().len(), 3);
+	assert_eq!(atlas.bin_list()[1].part_list().len(), 2);
+	assert_eq!(atlas.bin_list()[1].reserved_list()[0].x, 0);
+}
+
+#[test]
+fn test_pin_max_bins()
+{
+	// Rects pinned past the bin limit are not placed, even where the generator would have room.
+	let rect_list = vec![Rect::new(64, 64); 3];
+	let error = Atlas::build(&rect_list, 128, 128, false)
+		.max_bins(1)
+		.pin(2, 1, 0, 0, false)
+		.generate(&BinaryTreeGenerator::new()).err().unwrap();
+	assert_eq!(error.atlas().bin_list().len(), 1);
+	assert_eq!(error.atlas().bin_list()[0].part_list().len(), 2);
+	assert_eq!(error.unplaced_list(), &[2]);
+}
+
+#[test]
+fn test_optimal_reserved()
+{
+	smoke_reserved(&OptimalGenerator::new().node_limit(10_000));
+}
+
+#[test]
+fn test_annealing_reserved()
+{
+	smoke_reserved(&AnnealingGenerator::new(BinaryTreeGenerator::new()).iteration_limit(100));
+}
+
+fn smoke_max_bins<T: AtlasGenerator>(generator: &T)
+{
+	let rect_list = vec![Rect::new(128, 128), Rect::new(128, 128), Rect::new(256, 256), Rect::new(128, 128)];
+	let error = Atlas::build(&rect_list, 128, 128, false).max_bins(2).generate(generator).err().unwrap();
+	assert_eq!(error.atlas().bin_list().len(), 2);
+	assert_eq!(error.unplaced_list(), &[2, 3]);
+	assert_disjoint(error.atlas());
+}
+
+#[test]
+fn test_passthrough_max_bins()
+{
+	smoke_max_bins(&PassthroughGenerator);
+}
+
+#[test]
+fn test_binary_tree_max_bins()
+{
+	smoke_max_bins(&BinaryTreeGenerator::new());
+}
+
+#[test]
+fn test_optimal_max_bins()
+{
+	smoke_max_bins(&OptimalGenerator::new().node_limit(10_000));
+}
+
+#[test]
+fn test_annealing_max_bins()
+{
+	smoke_max_bins(&AnnealingGenerator::new(BinaryTreeGenerator::new()).iteration_limit(100));
+}
+
+#[test]
+fn test_array()
+{
+	let rect_list = vec![Rect::new(64, 32), Rect::new(128, 128), Rect::new(16, 16)];
+	let atlas = Atlas::build(&rect_list, 128, 128, false).array().generate(&BinaryTreeGenerator::new()).unwrap();
+	assert_eq!(atlas.bin_list().len(), 2);
+	for bin in atlas.bin_list()
+	{
+		let dimensions = bin.dimensions();
+		assert_eq!((dimensions.width, dimensions.height), (128, 128));
+	}
+
+	let uv = atlas.rect_uv(0).unwrap();
+	assert_eq!(uv.layer, 1);
+	assert_eq!((uv.u_min, uv.v_min, uv.u_max, uv.v_max), (0.0, 0.0, 0.5, 0.25));
+	assert_eq!(atlas.rect_uv(1).unwrap().layer, 0);
+}
+
+fn smoke_alignment<T: AtlasGenerator>(generator: &T)
+{
+	let rect_list = vec![Rect::new(5, 3), Rect::new(7, 7), Rect::new(1, 1), Rect::new(13, 2), Rect::new(4, 9)];
+	let atlas = Atlas::build(&rect_list, 32, 32, true).reserve(0, 0, 1, 1).block_compressed().generate(generator).unwrap();
+	assert_disjoint(&atlas);
+	for bin in atlas.bin_list()
+	{
+		assert_eq!(bin.dimensions().width % 4, 0);
+		assert_eq!(bin.dimensions().height % 4, 0);
+		for part in bin.part_list()
+		{
+			assert_eq!((part.x % 4, part.y % 4), (0, 0));
+			assert!(part.x != 0 || part.y != 0);
+		}
+	}
+
+	// The true content size is still reported.
+	let uv = atlas.rect_uv(2).unwrap();
+	let width = atlas.bin_list()[uv.layer].dimensions().width as f32;
+	assert!(((uv.u_max - uv.u_min) * width - 1.0).abs() < 1e-4);
+}
+
+#[test]
+fn test_passthrough_alignment()
+{
+	smoke_alignment(&PassthroughGenerator);
+}
+
+#[test]
+fn test_binary_tree_alignment()
+{
+	smoke_alignment(&BinaryTreeGenerator::new());
+}
+
+#[test]
+fn test_optimal_alignment()
+{
+	smoke_alignment(&OptimalGenerator::new().node_limit(10_000));
+}
+
+#[test]
+fn test_annealing_alignment()
+{
+	smoke_alignment(&AnnealingGenerator::new(BinaryTreeGenerator::new()).iteration_limit(100));
+}
+
+#[test]
+fn test_mip_levels()
+{
+	// The largest number of levels does not overflow the alignment or the padding.
+	let rect_list = vec![Rect::new(5, 3), Rect::new(7, 7)];
+	let atlas = Atlas::build(&rect_list, 1 << 18, 1 << 18, false).mip_levels(MAX_MIP_LEVELS).generate(&BinaryTreeGenerator::new()).unwrap();
+	assert_eq!((atlas.alignment(), atlas.padding()), (1 << 15, 1 << 16));
+	assert_disjoint(&atlas);
+}
+
+#[test]
+fn test_binary_tree_incremental()
+{
+	let rect_list = vec![Rect::new(64, 64), Rect::new(32, 32), Rect::new(32, 32), Rect::new(16, 48), Rect::new(8, 8)];
+	let previous = Atlas::build(&rect_list, 128, 128, false).generate(&BinaryTreeGenerator::new()).unwrap();
+
+	// Rect 2 is resized, a rect is removed and two rects are added.
+	let rect_list_new = vec![Rect::new(8, 8), Rect::new(64, 64), Rect::new(32, 32), Rect::new(40, 40), Rect::new(16, 48), Rect::new(24, 24)];
+	let previous_index_list = vec![Some(4), Some(0), Some(1), Some(2), Some(3), None];
+	let atlas = Atlas::build(&rect_list_new, 128, 128, false)
+		.incremental(&previous, &previous_index_list)
+		.generate(&BinaryTreeGenerator::new())
+		.unwrap();
+	assert_disjoint(&atlas);
+
+	for (rect_index, &previous_index) in previous_index_list.iter().enumerate()
+	{
+		if rect_index == 3 || previous_index.is_none()
+		{
+			continue;
+		}
+		let (bin_index, part) = atlas.rect_part(rect_index).unwrap();
+		let (previous_bin_index, previous_part) = previous.rect_part(previous_index.unwrap()).unwrap();
+		assert_eq!((bin_index, part.x, part.y), (previous_bin_index, previous_part.x, previous_part.y));
+	}
+
+	// A saved layout keeps the same placements as the atlas it was saved from.
+	let from_layout = Atlas::build(&rect_list_new, 128, 128, false)
+		.incremental_layout(&previous.layout(), &previous_index_list)
+		.generate(&BinaryTreeGenerator::new())
+		.unwrap();
+	assert_eq!(placement_list(&from_layout), placement_list(&atlas));
+}
+
+#[test]
+fn test_incremental_rotate()
+{
+	let rect_list = vec![Rect::new(8, 32), Rect::new(32, 8), Rect::new(16, 4)];
+	let previous = Atlas::build(&rect_list, 32, 32, true).generate(&BinaryTreeGenerator::new()).unwrap();
+	assert!(previous.bin_list().iter().flat_map(|bin| bin.part_list()).any(|part| part.rotate));
+
+	// Rotated placements are not kept when rotation is no longer allowed, and indices that are not
+	// in the previous atlas are treated as new rects.
+	let previous_index_list = vec![Some(0), Some(1), Some(100)];
+	let atlas = Atlas::build(&rect_list, 32, 32, false)
+		.incremental(&previous, &previous_index_list)
+		.generate(&BinaryTreeGenerator::new())
+		.unwrap();
+	assert_disjoint(&atlas);
+	assert!(!atlas.bin_list().iter().flat_map(|bin| bin.part_list()).any(|part| part.rotate));
+}
+
+fn placement_list<T: AtlasRect>(atlas: &Atlas<T>) -> Vec<(usize, usize, u32, u32, bool)>
+{
+	let mut placement_list = Vec::new();
+	for (bin_index, bin) in atlas.bin_list().iter().enumerate()
+	{
+		for part in bin.part_list()
+		{
+			placement_list.push((part.rect_index, bin_index, part.x, part.y, part.rotate));
+		}
+	}
+	placement_list
+}
+
+#[test]
+fn test_deterministic()
+{
+	// Equal rects are placed in order of their index.
+	let rect_list = vec![Rect::new(32, 16), Rect::new(16, 32), Rect::new(32, 16), Rect::new(16, 16), Rect::new(16, 32), Rect::new(16, 16), Rect::new(48, 8)];
+	let atlas = Atlas::build(&rect_list, 64, 64, true).generate(&BinaryTreeGenerator::new()).unwrap();
+	assert_eq!(placement_list(&atlas), vec![
+		(0, 0, 0, 0, false),
+		(1, 0, 32, 0, true),
+		(2, 0, 0, 16, true),
+		(4, 0, 16, 16, false),
+		(3, 0, 32, 16, false),
+		(5, 0, 48, 16, false),
+		(6, 0, 0, 48, false),
+	]);
+
+	let atlas = Atlas::build(&rect_list, 64, 64, true).generate(&PassthroughGenerator).unwrap();
+	let expected: Vec<_> = (0..rect_list.len()).map(|index| (index, index, 0, 0, false)).collect();
+	assert_eq!(placement_list(&atlas), expected);
+}
+
+#[test]
+fn test_rng()
+{
+	// Reference values of SplitMix64 seeded with 0.
+	let mut rng = Rng::new(0);
+	assert_eq!(rng.next_u64(), 0xE220_A839_7B1D_CDAF);
+	assert_eq!(rng.next_u64(), 0x6E78_9E6A_A1B9_65F4);
+
+	let mut list: Vec<usize> = (0..16).collect();
+	Rng::new(7).shuffle(&mut list);
+	let mut list_again: Vec<usize> = (0..16).collect();
+	Rng::new(7).shuffle(&mut list_again);
+	assert_eq!(list, list_again);
+	assert!(list != (0..16).collect::<Vec<_>>());
+}
+
+enum GeneratorChoice
+{
+	Passthrough,
+	BinaryTree,
+}
+
+impl AtlasGenerator for GeneratorChoice
+{
+	fn generate<T: AtlasRect>(&self, atlas: &mut Atlas<T>, width: u32, height: u32, rotate: bool)
+	{
+		match *self
+		{
+			GeneratorChoice::Passthrough => PassthroughGenerator.generate(atlas, width, height, rotate),
+			GeneratorChoice::BinaryTree => BinaryTreeGenerator::new().generate(atlas, width, height, rotate),
+		}
+	}
+}
+
+#[test]
+fn test_generate_best()
+{
+	let rect_list = vec![Rect::new(32, 32); 8];
+	let generator_list = [GeneratorChoice::Passthrough, GeneratorChoice::BinaryTree];
+	let atlas = Atlas::build(&rect_list, 64, 64, false).generate_best(&generator_list).unwrap();
+	assert_eq!(atlas.bin_list().len(), 2);
+
+	// The failure placing the most rects is reported.
+	let error = Atlas::build(&rect_list, 64, 64, false).max_bins(1).generate_best(&generator_list).err().unwrap();
+	assert_eq!(error.unplaced_list().len(), 4);
+}
+
+/// A rect that cannot be shared between threads.
+struct SharedRect(std::rc::Rc<Rect>);
+
+impl AsRef<Rect> for SharedRect
+{
+	fn as_ref(&self) -> &Rect
+	{
+		&self.0
+	}
+}
+
+#[test]
+fn test_generate_best_unsync()
+{
+	// Rects do not need to be `Sync`, whichever features are enabled.
+	let rect_list: Vec<SharedRect> = (0..8).map(|_| SharedRect(std::rc::Rc::new(Rect::new(32, 32)))).collect();
+	let atlas = Atlas::build(&rect_list, 64, 64, false).generate_best(&[BinaryTreeGenerator::new()]).unwrap();
+	assert_eq!(atlas.bin_list().len(), 2);
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_par_generate_best()
+{
+	let mut rng = Rng::new(5);
+	let rect_list: Vec<Rect> = (0..20).map(|_| Rect::new(1 + rng.below(30) as u32, 1 + rng.below(30) as u32)).collect();
+	let generator_list = [BinaryTreeGenerator::new(), BinaryTreeGenerator::new().sort(SortPolicy::Area)];
+	let atlas = Atlas::build(&rect_list, 64, 64, true).generate_best(&generator_list).unwrap();
+	let par_atlas = Atlas::build(&rect_list, 64, 64, true).par_generate_best(&generator_list).unwrap();
+	assert_eq!(placement_list(&par_atlas), placement_list(&atlas));
+}
+
+#[test]
+fn test_stats()
+{
+	let rect_list = vec![Rect::new(64, 64), Rect::new(64, 64), Rect::new(64, 64)];
+	let atlas = Atlas::build(&rect_list, 128, 128, false).generate(&BinaryTreeGenerator::new()).unwrap();
+	let stats = atlas.stats();
+	assert_eq!(stats.bin_list.len(), 1);
+	assert_eq!(stats.used_area, 3 * 64 * 64);
+	assert_eq!(stats.wasted_area, 64 * 64);
+	assert_eq!(stats.occupancy, 0.75);
+	assert_eq!(stats.part_count, 3);
+	assert_eq!(stats.rotated_count, 0);
+	assert_eq!(stats.lower_bound, Some(1));
+	assert_eq!(stats.excess_bins(), Some(0));
+	let (bin_index, largest_free) = stats.largest_free.unwrap();
+	assert_eq!((bin_index, largest_free.width, largest_free.height), (0, 64, 64));
+
+	// Atlases that were not generated by a builder have no lower bound.
+	assert_eq!(Atlas::new(&rect_list).stats().lower_bound, None);
+}
+
+#[test]
+fn test_largest_free()
+{
+	let occupied_list = [Region::new(0, 0, 10, 3), Region::new(0, 3, 4, 7)];
+	let free = util::region_largest_free(Rect::new(10, 10), &occupied_list);
+	assert_eq!((free.x, free.y, free.width, free.height), (4, 3, 6, 7));
+
+	let occupied_list = [Region::new(4, 4, 2, 2)];
+	let free = util::region_largest_free(Rect::new(10, 10), &occupied_list);
+	assert_eq!((free.x, free.y, free.width, free.height), (0, 0, 10, 4));
+
+	let free = util::region_largest_free(Rect::new(10, 10), &[Region::new(0, 0, 20, 20)]);
+	assert_eq!(free.width * free.height, 0);
+}
+
+#[test]
+fn test_lower_bound()
+{
+	let size = Rect::new(100, 100);
+
+	// The area bound no longer overshoots when the area divides evenly.
+	assert_eq!(util::lower_bound(&vec![Rect::new(50, 50); 4], size, false), 1);
+	assert_eq!(util::lower_bound(&[], size, false), 0);
+
+	// Rects larger than half of the bin each need their own bin.
+	assert_eq!(util::lower_bound(&vec![Rect::new(60, 60); 3], size, false), 3);
+	assert_eq!(util::lower_bound(&vec![Rect::new(60, 60); 3], size, true), 3);
+
+	// Rects that are only tall can be rotated next to each other.
+	assert_eq!(util::lower_bound(&vec![Rect::new(20, 60); 6], size, false), 2);
+	assert_eq!(util::lower_bound(&vec![Rect::new(20, 60); 6], size, true), 1);
+
+	// Small rects do not fit next to the large rects.
+	let rect_list = [Rect::new(70, 70), Rect::new(70, 70), Rect::new(40, 40), Rect::new(40, 40)];
+	assert_eq!(util::lower_bound_area(&rect_list, size), 2);
+	assert_eq!(util::lower_bound(&rect_list, size, false), 3);
+	let atlas = Atlas::build(&rect_list, 100, 100, false).generate(&BinaryTreeGenerator::new()).unwrap();
+	assert_eq!(atlas.lower_bound(), Some(3));
+	assert_eq!(atlas.bin_list().len(), 3);
+}
+
+#[test]
+fn test_optimal_bins()
+{
+	// The lower bound is reached with one bin less than the binary tree generator.
+	let corpus = corpus::berkey_wang(1, 20, 101);
+	let heuristic = Atlas::build(&corpus.rect_list, corpus.width, corpus.height, false).generate(&BinaryTreeGenerator::new()).unwrap();
+	let atlas = Atlas::build(&corpus.rect_list, corpus.width, corpus.height, false).generate(&OptimalGenerator::new()).unwrap();
+	assert_eq!(heuristic.bin_list().len(), 7);
+	assert_eq!(atlas.bin_list().len(), 6);
+	assert_eq!(atlas.lower_bound(), Some(6));
+	assert!(atlas.rect_placed_list().iter().all(|&placed| placed));
+	assert_disjoint(&atlas);
+
+	// Without any budget, the binary tree generator result is kept.
+	let atlas = Atlas::build(&corpus.rect_list, corpus.width, corpus.height, false).generate(&OptimalGenerator::new().node_limit(0)).unwrap();
+	assert_eq!(placement_list(&atlas), placement_list(&heuristic));
+}
+
+#[test]
+fn test_optimal_area()
+{
+	// Everything fits in a single bin, which is then made smaller.
+	let corpus = corpus::berkey_wang(2, 20, 102);
+	let heuristic = Atlas::build(&corpus.rect_list, corpus.width, corpus.height, true).generate(&BinaryTreeGenerator::new()).unwrap();
+	let atlas = Atlas::build(&corpus.rect_list, corpus.width, corpus.height, true).generate(&OptimalGenerator::new()).unwrap();
+	assert_eq!(atlas.bin_list().len(), 1);
+	assert!((&atlas.bin_list()[0] as &AtlasRect).area() < (&heuristic.bin_list()[0] as &AtlasRect).area());
+	assert!(atlas.rect_placed_list().iter().all(|&placed| placed));
+	assert_disjoint(&atlas);
+	for region in atlas.bin_occupied_list(0)
+	{
+		assert!(region.right() <= corpus.width && region.bottom() <= corpus.height);
+	}
+}
+
+#[test]
+fn test_annealing_improves()
+{
+	let corpus = corpus::martello_vigo(8, 100, 8);
+	let build = || Atlas::build(&corpus.rect_list, corpus.width, corpus.height, true);
+	let heuristic = build().generate(&BinaryTreeGenerator::new()).unwrap();
+	let atlas = build().generate(&AnnealingGenerator::new(BinaryTreeGenerator::new()).seed(1)).unwrap();
+	assert!(atlas.bin_list().len() < heuristic.bin_list().len());
+	assert!(atlas.rect_placed_list().iter().all(|&placed| placed));
+	assert_disjoint(&atlas);
+
+	// The same seed always gives the same bins.
+	let repeated = build().generate(&AnnealingGenerator::new(BinaryTreeGenerator::new()).seed(1)).unwrap();
+	assert_eq!(placement_list(&atlas), placement_list(&repeated));
+
+	// Without any iterations, the inner generator result is kept.
+	let atlas = build().generate(&AnnealingGenerator::new(BinaryTreeGenerator::new()).iteration_limit(0)).unwrap();
+	assert_eq!(placement_list(&atlas), placement_list(&heuristic));
+}
+
+#[test]
+fn test_sort_policy()
+{
+	let policy_list = [SortPolicy::Height, SortPolicy::Width, SortPolicy::Area, SortPolicy::Perimeter, SortPolicy::MaxSide,
+		SortPolicy::SideRatio, SortPolicy::None];
+	for &policy in &policy_list
+	{
+		smoke(&BinaryTreeGenerator::new().sort(policy));
+	}
+
+	let rect_list = vec![Rect::new(4, 12), Rect::new(10, 2), Rect::new(6, 6), Rect::new(8, 5), Rect::new(2, 10)];
+	let atlas = Atlas::new(&rect_list);
+	let order = |policy: SortPolicy, rotate: bool|
+	{
+		BinaryTreeGenerator::new().sort(policy).order_list(&atlas, rotate).iter().map(|&(index, _)| index).collect::<Vec<_>>()
+	};
+	assert_eq!(order(SortPolicy::Height, false), vec![0, 4, 2, 3, 1]);
+	assert_eq!(order(SortPolicy::Width, false), vec![1, 3, 2, 0, 4]);
+	assert_eq!(order(SortPolicy::Area, false), vec![0, 3, 2, 1, 4]);
+	assert_eq!(order(SortPolicy::Perimeter, false), vec![0, 3, 1, 4, 2]);
+	assert_eq!(order(SortPolicy::MaxSide, false), vec![0, 1, 4, 3, 2]);
+	assert_eq!(order(SortPolicy::SideRatio, false), vec![1, 4, 0, 3, 2]);
+	assert_eq!(order(SortPolicy::None, false), vec![0, 1, 2, 3, 4]);
+
+	// Rects are rotated to lie flat first when rotations are allowed.
+	assert_eq!(order(SortPolicy::Height, true), vec![2, 3, 0, 1, 4]);
+	assert!(BinaryTreeGenerator::new().order_list(&atlas, true)[2].1);
+}
+
+#[test]
+fn test_binary_tree_rotate()
+{
+	// The first rect of a bin is placed rotated when only that fits.
+	let rect_list = vec![Rect::new(10, 20)];
+	let atlas = Atlas::build(&rect_list, 20, 10, true).generate(&BinaryTreeGenerator::new()).unwrap();
+	assert_eq!(atlas.bin_list().len(), 1);
+	assert!(atlas.bin_list()[0].part_list()[0].rotate);
+
+	// Leaves try the other orientation before opening another bin.
+	let rect_list = vec![Rect::new(12, 12), Rect::new(12, 8), Rect::new(20, 8)];
+	let atlas = Atlas::build(&rect_list, 20, 20, true).generate(&BinaryTreeGenerator::new()).unwrap();
+	assert_disjoint(&atlas);
+	assert_eq!(atlas.bin_list().len(), 1);
+	assert!(placement_list(&atlas).contains(&(1, 0, 12, 0, true)));
+}
+
+#[test]
+fn test_split_policy()
+{
+	let policy_list = [SplitPolicy::Horizontal, SplitPolicy::Vertical, SplitPolicy::ShorterLeftoverAxis,
+		SplitPolicy::LongerLeftoverAxis, SplitPolicy::MinimizeArea, SplitPolicy::MaximizeArea];
+	for &policy in &policy_list
+	{
+		smoke(&BinaryTreeGenerator::new().split(policy));
+		smoke_reserved(&BinaryTreeGenerator::new().split(policy));
+	}
+
+	// Only a horizontal split leaves a full width leaf below the first rect.
+	let rect_list = vec![Rect::new(4, 6), Rect::new(10, 4)];
+	let bin_count = |policy: SplitPolicy|
+	{
+		let generator = BinaryTreeGenerator::new().split(policy);
+		Atlas::build(&rect_list, 10, 10, false).generate(&generator).unwrap().bin_list().len()
+	};
+	assert_eq!(bin_count(SplitPolicy::Horizontal), 1);
+	assert_eq!(bin_count(SplitPolicy::Vertical), 2);
+	assert_eq!(bin_count(SplitPolicy::ShorterLeftoverAxis), 2);
+	assert_eq!(bin_count(SplitPolicy::LongerLeftoverAxis), 1);
+}
+
+#[test]
+fn test_registry()
+{
+	let registry = GeneratorRegistry::new();
+	assert_eq!(registry.name_list(), vec!["annealing", "binary_tree", "optimal", "passthrough"]);
+	for name in registry.name_list()
+	{
+		let generator = registry.create(name, &GeneratorParams::new()).unwrap();
+		smoke(&generator);
+		smoke_reserved(&generator);
+		smoke_alignment(&generator);
+	}
+
+	// Boxed generators place rects like the generators they box.
+	let mut rng = Rng::new(3);
+	let rect_list: Vec<Rect> = (0..100).map(|_| Rect::new(1 + rng.below(40) as u32, 1 + rng.below(40) as u32)).collect();
+	let generator = registry.parse("binary_tree: sort=area, split=vertical").unwrap();
+	let atlas = Atlas::build(&rect_list, 128, 128, true).generate(&generator).unwrap();
+	let expected_generator = BinaryTreeGenerator::new().sort(SortPolicy::Area).split(SplitPolicy::Vertical);
+	let expected = Atlas::build(&rect_list, 128, 128, true).generate(&expected_generator).unwrap();
+	assert_eq!(placement_list(&atlas), placement_list(&expected));
+
+	let error = |text: &str| registry.parse(text).err().unwrap();
+	assert_eq!(error("max_rects"), GeneratorError::UnknownGenerator("max_rects".to_string()));
+	assert_eq!(error("passthrough:sort=area"), GeneratorError::UnknownParam("sort".to_string()));
+	assert_eq!(error("binary_tree:sort=random"), GeneratorError::InvalidParam("sort".to_string(), "random".to_string()));
+	assert_eq!(error("optimal:node_limit=-1"), GeneratorError::InvalidParam("node_limit".to_string(), "-1".to_string()));
+	assert_eq!(error("annealing:seed"), GeneratorError::Syntax("seed".to_string()));
+
+	// Custom generators can be registered alongside the built-in generators.
+	let registry = GeneratorRegistry::new().register("boxed_passthrough", |params|
+	{
+		params.check(&[])?;
+		Ok(Box::new(PassthroughGenerator) as Box<dyn DynGenerator>)
+	});
+	let generator_list = vec![registry.parse("boxed_passthrough").unwrap(), registry.parse("optimal:node_limit=1000,time_limit=100").unwrap()];
+	let atlas = Atlas::build(&rect_list[..10], 128, 128, false).generate_best(&generator_list).unwrap();
+	assert_eq!(atlas.bin_list().len(), 1);
+}
+
+fn owned_load(seed: u64) -> OwnedAtlas<Rect>
+{
+	let mut rng = Rng::new(seed);
+	let rect_list: Vec<Rect> = (0..50).map(|_| Rect::new(1 + rng.below(40) as u32, 1 + rng.below(40) as u32)).collect();
+	Atlas::build_owned(rect_list, 128, 128, true).generate(&BinaryTreeGenerator::new()).unwrap()
+}
+
+#[test]
+fn test_owned()
+{
+	// Owned atlases outlive the function that loaded their rects.
+	let owned = owned_load(5);
+	assert!(owned.is_owned());
+	let rect_list = owned.rect_list().to_vec();
+	let atlas = Atlas::build(&rect_list, 128, 128, true).generate(&BinaryTreeGenerator::new()).unwrap();
+	assert!(!atlas.is_owned());
+	assert_eq!(placement_list(&owned), placement_list(&atlas));
+
+	// Conversions keep the bins.
+	let borrowed = owned.as_borrowed();
+	assert!(!borrowed.is_owned());
+	assert_eq!(placement_list(&borrowed), placement_list(&owned));
+	let converted = atlas.into_owned();
+	assert!(converted.is_owned());
+	assert_eq!(placement_list(&converted), placement_list(&owned));
+
+	// Owned atlases can be sent to other threads.
+	let part_count = std::thread::spawn(move || owned.stats().part_count).join().unwrap();
+	assert_eq!(part_count, rect_list.len());
+
+	// Errors of owned builders keep the rects too.
+	let error = Atlas::build_owned(rect_list.clone(), 64, 64, false)
+		.max_bins(1)
+		.generate_best(&[BinaryTreeGenerator::new(), BinaryTreeGenerator::new().sort(SortPolicy::Area)])
+		.err()
+		.unwrap();
+	assert!(error.atlas().is_owned());
+	assert!(!error.unplaced_list().is_empty());
+}